@@ -0,0 +1 @@
+//! Only exists for its tests, see `tests/embassy_timeout.rs`.
@@ -0,0 +1,117 @@
+//! The `runtime = "embassy"` expansion, run against the real embassy-time with its std driver.
+//!
+//! `tokio-timeout/tests/embassy_timeout.rs` runs the same expansion against a stand-in on the
+//! workspace's MSRV, and also checks the microseconds handed to `with_timeout`.
+use embassy_time::{Duration, Timer};
+use std::sync::atomic::{AtomicU32, Ordering};
+use tokio_timeout::timeout;
+
+#[timeout(duration = "1s", on_error = "panic", runtime = "embassy")]
+pub async fn my_fast_fn() -> u32 {
+    1
+}
+
+#[timeout(duration = "1ms", on_error = "panic", runtime = "embassy")]
+pub async fn my_panicking_fn() {
+    Timer::after(Duration::from_secs(1)).await;
+}
+
+#[timeout(duration = "1ms", on_error = "into", runtime = "embassy")]
+pub async fn my_into_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    Timer::after(Duration::from_secs(1)).await;
+    Ok(())
+}
+
+fn ctx_err(
+    ctx: tokio_timeout::TimeoutContext<embassy_time::TimeoutError>,
+) -> Result<(), tokio_timeout::TimeoutContext<embassy_time::TimeoutError>> {
+    Err(ctx)
+}
+
+#[timeout(duration = "1ms", on_error_ctx = ctx_err, runtime = "embassy")]
+pub async fn my_ctx_fn() -> Result<(), tokio_timeout::TimeoutContext<embassy_time::TimeoutError>> {
+    Timer::after(Duration::from_secs(1)).await;
+    Ok(())
+}
+
+#[timeout(duration = "1500ns", on_error = "into", runtime = "embassy")]
+pub async fn my_sub_micro_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    Timer::after(Duration::from_secs(1)).await;
+    Ok(())
+}
+
+const ATTEMPT: std::time::Duration = std::time::Duration::from_nanos(5_000_500);
+
+#[timeout(duration = ATTEMPT, on_error = "panic", runtime = "embassy")]
+pub async fn my_const_fn() -> u32 {
+    3
+}
+
+static FLAKY_CALLS: AtomicU32 = AtomicU32::new(0);
+
+#[timeout(
+    duration = "5ms",
+    on_error = "panic",
+    retries = 2,
+    backoff = "1ms",
+    total = "1s",
+    runtime = "embassy"
+)]
+pub async fn my_flaky_fn() -> u32 {
+    let call = FLAKY_CALLS.fetch_add(1, Ordering::SeqCst) + 1;
+    if call < 3 {
+        Timer::after(Duration::from_secs(1)).await;
+    }
+    call
+}
+
+static SLOW_WARNINGS: AtomicU32 = AtomicU32::new(0);
+
+fn count_slow(_msg: &'static str) {
+    SLOW_WARNINGS.fetch_add(1, Ordering::SeqCst);
+}
+
+#[timeout(duration = "1s", on_error = "panic", warn_after = "1ms", on_slow = count_slow, runtime = "embassy")]
+pub async fn my_slow_fn() -> u32 {
+    Timer::after(Duration::from_millis(10)).await;
+    4
+}
+
+#[timeout(duration = "none", on_error = "panic", runtime = "embassy")]
+pub async fn my_unbounded_fn() -> u32 {
+    Timer::after(Duration::from_millis(10)).await;
+    5
+}
+
+#[tokio::test]
+async fn completes_in_time() {
+    assert_eq!(1, my_fast_fn().await);
+    assert_eq!(3, my_const_fn().await);
+    assert_eq!(5, my_unbounded_fn().await);
+}
+
+#[tokio::test]
+#[should_panic(expected = "'my_panicking_fn' timed out after 1ms")]
+async fn panics_on_timeout() {
+    my_panicking_fn().await;
+}
+
+#[tokio::test]
+async fn errors_on_timeout() {
+    let err = my_into_fn().await.unwrap_err();
+    assert_eq!("my_into_fn", err.fn_name());
+    assert_eq!(std::time::Duration::from_millis(1), err.duration());
+    let ctx = my_ctx_fn().await.unwrap_err();
+    assert_eq!("my_ctx_fn", ctx.fn_name);
+    assert_eq!(embassy_time::TimeoutError, ctx.error);
+    assert!(ctx.elapsed >= std::time::Duration::from_millis(1));
+    let err = my_sub_micro_fn().await.unwrap_err();
+    assert_eq!(std::time::Duration::from_nanos(1500), err.duration());
+}
+
+#[tokio::test]
+async fn retries_and_warns() {
+    assert_eq!(3, my_flaky_fn().await);
+    assert_eq!(4, my_slow_fn().await);
+    assert_eq!(1, SLOW_WARNINGS.load(Ordering::SeqCst));
+}
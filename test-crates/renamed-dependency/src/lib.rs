@@ -0,0 +1 @@
+//! Only exists for its tests, see `tests/renamed_timeout.rs`.
@@ -0,0 +1,49 @@
+//! tokio-timeout renamed to `tt` in Cargo.toml, expansions that don't need the crate's types
+//! can't refer to `::tokio_timeout`, which doesn't resolve here.
+use std::time::Duration;
+use tt::timeout;
+
+const SOME_CONST: Duration = Duration::from_millis(1);
+
+fn my_err(msg: &'static str) -> Result<u32, String> {
+    Err(msg.to_string())
+}
+
+#[timeout(duration = SOME_CONST, on_error = my_err)]
+pub async fn my_const_fn() -> Result<u32, String> {
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+    Ok(1)
+}
+
+#[timeout(duration = SOME_CONST, on_error = "panic")]
+pub async fn my_fast_const_fn() -> u32 {
+    2
+}
+
+#[timeout(duration = timeout, on_error = "default")]
+pub async fn my_optional_fn(timeout: Option<Duration>) -> u32 {
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    3
+}
+
+#[timeout(duration = "1ms", on_error = "panic")]
+pub async fn my_panicking_fn() {
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+}
+
+#[tokio::test]
+async fn expression_durations() {
+    assert_eq!(
+        "'my_const_fn' timed out after duration from SOME_CONST",
+        my_const_fn().await.unwrap_err()
+    );
+    assert_eq!(2, my_fast_const_fn().await);
+    assert_eq!(3, my_optional_fn(None).await);
+    assert_eq!(0, my_optional_fn(Some(Duration::from_millis(1))).await);
+}
+
+#[tokio::test]
+#[should_panic(expected = "'my_panicking_fn' timed out after 1ms")]
+async fn literal_durations() {
+    my_panicking_fn().await;
+}
@@ -0,0 +1,28 @@
+//! With the `tokio` feature the expanded code finds tokio through `tokio-timeout`, without a
+//! direct dependency on it.
+use std::time::Duration;
+use tokio_timeout::timeout;
+
+#[timeout(duration = "5ms", on_error = "into")]
+pub async fn my_sleeping_fn(sleep: Duration) -> Result<(), tokio_timeout::TimeoutError> {
+    tokio_timeout::__private::tokio::time::sleep(sleep).await;
+    Ok(())
+}
+
+#[timeout(duration = "5ms", on_error = "panic", retries = 1, backoff = "1ms")]
+pub async fn my_panicking_fn() {
+    tokio_timeout::__private::tokio::time::sleep(Duration::from_secs(1)).await;
+}
+
+#[rt::test(crate = "rt")]
+async fn uses_the_reexport() {
+    assert!(my_sleeping_fn(Duration::ZERO).await.is_ok());
+    let err = my_sleeping_fn(Duration::from_secs(1)).await.unwrap_err();
+    assert_eq!(Duration::from_millis(5), err.duration());
+}
+
+#[rt::test(crate = "rt")]
+#[should_panic(expected = "'my_panicking_fn' timed out after 5ms")]
+async fn panics_on_timeout() {
+    my_panicking_fn().await;
+}
@@ -0,0 +1,691 @@
+use crate::types::{ParseDurationError, ParseDurationErrorKind};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use ParseDurationErrorKind as Kind;
+
+fn err(kind: ParseDurationErrorKind, message: String) -> ParseDurationError {
+    ParseDurationError::new(kind, message)
+}
+
+/// Parses either a sequence of `<number><unit>` components, optionally separated by whitespace,
+/// such as `"1h30m"` or `"1 hour 30 minutes"`, or an ISO-8601 duration such as `"PT1H30M"`,
+/// detected by its leading `P`
+///
+/// # Errors
+/// If the string isn't a valid, non-zero, duration
+pub fn parse_duration(dur: &str) -> Result<Duration, ParseDurationError> {
+    parse(dur, false)
+}
+
+/// Like [`parse_duration`], but each unit may appear only once, in descending order,
+/// so that `"1m1m"` or `"1s1m"` are rejected
+///
+/// # Errors
+/// If the string isn't a valid, non-zero, duration with its units in order
+pub fn parse_duration_strict(dur: &str) -> Result<Duration, ParseDurationError> {
+    parse(dur, true)
+}
+
+/// Like [`parse_duration`], but `"none"` or `"infinite"` mean no timeout at all, giving `None`
+///
+/// # Errors
+/// If the string is neither unbounded nor a valid, non-zero, duration
+pub fn parse_timeout(dur: &str) -> Result<Option<Duration>, ParseDurationError> {
+    if is_unbounded(dur) {
+        Ok(None)
+    } else {
+        parse_duration(dur).map(Some)
+    }
+}
+
+/// Whether the duration is `"none"` or `"infinite"`, ignoring surrounding whitespace
+fn is_unbounded(dur: &str) -> bool {
+    matches!(dur.trim(), "none" | "infinite")
+}
+
+fn parse(dur: &str, strict: bool) -> Result<Duration, ParseDurationError> {
+    // ISO-8601 durations already require each designator once, in order
+    let parsed_dur = match dur.strip_prefix('P') {
+        Some(iso) => parse_iso8601(dur, iso)?,
+        None => parse_components(dur, strict)?,
+    };
+    if parsed_dur == Duration::ZERO {
+        Err(err(
+            Kind::Zero,
+            format!("parsing '{dur}' resulted in a zero duration"),
+        ))
+    } else {
+        Ok(parsed_dur)
+    }
+}
+
+fn parse_components(dur: &str, strict: bool) -> Result<Duration, ParseDurationError> {
+    let mut rest = dur.trim_start();
+    let mut parsed_dur = Duration::ZERO;
+    let mut prev_unit = None;
+    while !rest.is_empty() {
+        let (num, after_num) = split_at_first(rest, |c| !(c.is_ascii_digit() || c == '.'));
+        if num.is_empty() {
+            return Err(err(
+                Kind::InvalidNumber,
+                format!("failed to parse duration from '{dur}', expected a number at '{rest}'"),
+            ));
+        }
+        let after_num = after_num.trim_start();
+        let (unit, after_unit) = split_at_first(after_num, |c| !c.is_alphabetic());
+        if unit.is_empty() {
+            return Err(err(
+                Kind::MissingUnit,
+                if after_num.is_empty() {
+                    format!("parsing '{dur}' resulted in an unfinished calculation")
+                } else {
+                    format!(
+                        "failed to parse duration from '{dur}', expected a unit at '{after_num}'"
+                    )
+                },
+            ));
+        }
+        let accepted_unit = parse_unit(unit)?;
+        if strict && prev_unit.is_some_and(|prev| accepted_unit <= prev) {
+            let component = &rest[..rest.len() - after_unit.len()];
+            return Err(err(Kind::UnitOrder, format!(
+                "'{component}' in '{dur}' repeats a unit or comes after a smaller one, strict durations need each unit at most once, in descending order"
+            )));
+        }
+        prev_unit = Some(accepted_unit);
+        let add_dur = create_duration(&parse_num(num)?, accepted_unit)?;
+        parsed_dur = parsed_dur
+            .checked_add(add_dur)
+            .ok_or_else(|| err(Kind::Overflow, format!("'{dur}' overflows a duration")))?;
+        rest = after_unit.trim_start();
+    }
+    Ok(parsed_dur)
+}
+
+/// Parses the part after the leading `P` of an ISO-8601 duration, `[nW][nD][T[nH][nM][nS]]`.
+/// Years and months are rejected since their length depends on the calendar.
+fn parse_iso8601(dur: &str, iso: &str) -> Result<Duration, ParseDurationError> {
+    let (date, time) = match iso.split_once('T') {
+        Some((_, "")) => {
+            return Err(err(
+                Kind::InvalidIso8601,
+                format!("ISO-8601 duration '{dur}' has no components after 'T'"),
+            ))
+        }
+        Some((date, time)) => (date, time),
+        None => (iso, ""),
+    };
+    if date.is_empty() && time.is_empty() {
+        return Err(err(
+            Kind::InvalidIso8601,
+            format!("ISO-8601 duration '{dur}' has no components"),
+        ));
+    }
+    let date_units = [('W', AcceptedUnits::Week), ('D', AcceptedUnits::Day)];
+    let time_units = [
+        ('H', AcceptedUnits::Hour),
+        ('M', AcceptedUnits::Minute),
+        ('S', AcceptedUnits::Second),
+    ];
+    let mut parsed_dur = Duration::ZERO;
+    for (mut rest, units) in [(date, &date_units[..]), (time, &time_units[..])] {
+        // Designators have to come in order, and at most once
+        let mut allowed = units.iter();
+        while !rest.is_empty() {
+            let (num, after_num) =
+                split_at_first(rest, |c| !(c.is_ascii_digit() || c == '.' || c == ','));
+            let Some(designator) = after_num.chars().next() else {
+                return Err(err(
+                    Kind::InvalidIso8601,
+                    format!("ISO-8601 duration '{dur}' is missing a designator after '{num}'"),
+                ));
+            };
+            if num.is_empty() {
+                return Err(err(
+                    Kind::InvalidIso8601,
+                    format!("ISO-8601 duration '{dur}' is missing a number before '{designator}'"),
+                ));
+            }
+            let Some(&(_, unit)) = allowed.find(|(d, _)| *d == designator) else {
+                return Err(err(Kind::InvalidIso8601, if units.len() == 2 && matches!(designator, 'Y' | 'M') {
+                    format!("ISO-8601 duration '{dur}' uses years or months, which depend on the calendar")
+                } else {
+                    format!("unexpected or out of order designator '{designator}' in ISO-8601 duration '{dur}'")
+                }));
+            };
+            // ISO-8601 allows both '.' and ',' as the decimal sign
+            let add_dur = create_duration(&parse_num(&num.replace(',', "."))?, unit)?;
+            parsed_dur = parsed_dur
+                .checked_add(add_dur)
+                .ok_or_else(|| err(Kind::Overflow, format!("'{dur}' overflows a duration")))?;
+            rest = &after_num[designator.len_utf8()..];
+        }
+    }
+    Ok(parsed_dur)
+}
+
+/// Displays a duration normalised into days, hours, minutes and fractional seconds,
+/// or the largest fitting sub-second unit, such as `1h2m`, `1.5s` or `250us`.
+/// The output parses back into the same duration, even in strict mode.
+#[derive(Debug, Copy, Clone)]
+pub struct HumanDuration(pub Duration);
+
+impl Display for HumanDuration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let secs = self.0.as_secs();
+        let nanos = self.0.subsec_nanos();
+        if secs == 0 && nanos == 0 {
+            return f.write_str("0s");
+        }
+        for (amount, unit) in [
+            (secs / 86_400, 'd'),
+            (secs / 3_600 % 24, 'h'),
+            (secs / 60 % 60, 'm'),
+        ] {
+            if amount > 0 {
+                write!(f, "{amount}{unit}")?;
+            }
+        }
+        let secs = secs % 60;
+        if secs > 0 {
+            write!(f, "{secs}")?;
+            write_fraction(f, nanos, 9)?;
+            f.write_str("s")
+        } else if nanos >= 1_000_000 {
+            write!(f, "{}", nanos / 1_000_000)?;
+            write_fraction(f, nanos % 1_000_000, 6)?;
+            f.write_str("ms")
+        } else if nanos >= 1_000 {
+            write!(f, "{}", nanos / 1_000)?;
+            write_fraction(f, nanos % 1_000, 3)?;
+            f.write_str("us")
+        } else if nanos > 0 {
+            write!(f, "{nanos}ns")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Writes `.<frac>`, zero padded to `digits` with trailing zeros removed, if `frac` isn't zero
+fn write_fraction(f: &mut Formatter<'_>, frac: u32, digits: usize) -> std::fmt::Result {
+    if frac == 0 {
+        return Ok(());
+    }
+    let padded = format!("{frac:0digits$}");
+    write!(f, ".{}", padded.trim_end_matches('0'))
+}
+
+/// Splits `s` before the first character matching `pat`, or returns all of `s` if none does
+fn split_at_first(s: &str, pat: impl Fn(char) -> bool) -> (&str, &str) {
+    s.split_at(s.find(pat).unwrap_or(s.len()))
+}
+
+/// A decimal number, `whole.frac`, where `frac` has `frac_digits` significant digits
+struct Number {
+    whole: u64,
+    frac: u64,
+    frac_digits: u32,
+}
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+fn parse_num(sect: &str) -> Result<Number, ParseDurationError> {
+    if sect.is_empty() {
+        return Err(err(
+            Kind::InvalidNumber,
+            "failed to parse num, empty section".to_string(),
+        ));
+    };
+    let (whole, frac) = match sect.split_once('.') {
+        Some((whole, frac)) => {
+            if whole.is_empty() || frac.is_empty() {
+                return Err(err(
+                    Kind::InvalidNumber,
+                    format!(
+                        "failed to parse num from '{sect}': expected digits on both sides of '.'"
+                    ),
+                ));
+            }
+            (whole, frac)
+        }
+        None => (sect, ""),
+    };
+    if !frac.chars().all(|c| c.is_ascii_digit()) {
+        return Err(err(
+            Kind::InvalidNumber,
+            format!("failed to parse num from '{sect}': invalid fraction '{frac}'"),
+        ));
+    }
+    let whole = whole.parse().map_err(|e| {
+        err(
+            Kind::InvalidNumber,
+            format!("failed to parse num from '{sect}': {e}"),
+        )
+    })?;
+    // Trailing zeros carry no precision
+    let frac = frac.trim_end_matches('0');
+    // More than 19 significant fractional digits is beyond nanoseconds for any unit
+    if frac.len() > 19 {
+        return Err(err(
+            Kind::TooPrecise,
+            format!("'{sect}' is more precise than nanoseconds, which is the smallest supported precision"),
+        ));
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    let frac_digits = frac.len() as u32;
+    let frac = if frac.is_empty() {
+        0
+    } else {
+        frac.parse().map_err(|e| {
+            err(
+                Kind::InvalidNumber,
+                format!("failed to parse num from '{sect}': {e}"),
+            )
+        })?
+    };
+    Ok(Number {
+        whole,
+        frac,
+        frac_digits,
+    })
+}
+
+fn create_duration(num: &Number, unit: AcceptedUnits) -> Result<Duration, ParseDurationError> {
+    let unit_nanos = match unit {
+        AcceptedUnits::Year => 31_557_600 * NANOS_PER_SEC,
+        AcceptedUnits::Month => 2_630_016 * NANOS_PER_SEC,
+        AcceptedUnits::Week => 7 * 24 * 60 * 60 * NANOS_PER_SEC,
+        AcceptedUnits::Day => 24 * 60 * 60 * NANOS_PER_SEC,
+        AcceptedUnits::Hour => 60 * 60 * NANOS_PER_SEC,
+        AcceptedUnits::Minute => 60 * NANOS_PER_SEC,
+        AcceptedUnits::Second => NANOS_PER_SEC,
+        AcceptedUnits::Millisecond => NANOS_PER_SEC / 1_000,
+        AcceptedUnits::Microsecond => NANOS_PER_SEC / 1_000_000,
+        AcceptedUnits::Nanosecond => 1,
+    };
+    // Can't overflow, a u64 times a unit that fits in a u64 fits in a u128
+    let whole_nanos = u128::from(num.whole) * unit_nanos;
+    let frac_scaled = u128::from(num.frac) * unit_nanos;
+    let divisor = 10u128.pow(num.frac_digits);
+    if frac_scaled % divisor != 0 {
+        return Err(err(
+            Kind::TooPrecise,
+            format!(
+                "'{}.{:0width$}' is more precise than nanoseconds, which is the smallest supported precision",
+                num.whole,
+                num.frac,
+                width = num.frac_digits as usize
+            ),
+        ));
+    }
+    let nanos = whole_nanos + frac_scaled / divisor;
+    let secs = u64::try_from(nanos / NANOS_PER_SEC).map_err(|_| {
+        err(
+            Kind::Overflow,
+            format!("'{}' overflows a duration", num.whole),
+        )
+    })?;
+    // Always less than a second of nanos
+    #[allow(clippy::cast_possible_truncation)]
+    let subsec_nanos = (nanos % NANOS_PER_SEC) as u32;
+    Ok(Duration::new(secs, subsec_nanos))
+}
+
+/// Unit names are the same as `humantime`'s, plus the short forms `us` and `µs`.
+/// Months and years are, like in `humantime`, 30.44 and 365.25 days.
+fn parse_unit(unit: &str) -> Result<AcceptedUnits, ParseDurationError> {
+    Ok(match unit {
+        "years" | "year" | "y" => AcceptedUnits::Year,
+        "months" | "month" | "M" => AcceptedUnits::Month,
+        "weeks" | "week" | "w" => AcceptedUnits::Week,
+        "days" | "day" | "d" => AcceptedUnits::Day,
+        "hours" | "hour" | "hrs" | "hr" | "h" => AcceptedUnits::Hour,
+        "minutes" | "minute" | "mins" | "min" | "m" => AcceptedUnits::Minute,
+        "seconds" | "second" | "secs" | "sec" | "s" => AcceptedUnits::Second,
+        "milliseconds" | "millisecond" | "millis" | "msecs" | "msec" | "ms" => {
+            AcceptedUnits::Millisecond
+        }
+        // Both the micro sign and the greek small letter mu
+        "microseconds" | "microsecond" | "micros" | "usecs" | "usec" | "us" | "\u{b5}s"
+        | "\u{3bc}s" => AcceptedUnits::Microsecond,
+        "nanoseconds" | "nanosecond" | "nanos" | "nsecs" | "nsec" | "ns" => {
+            AcceptedUnits::Nanosecond
+        }
+        unk => return Err(err(Kind::UnknownUnit, format!("unknown unit: '{unk}'"))),
+    })
+}
+
+/// Ordered from the largest to the smallest unit
+#[derive(Copy, Clone, PartialEq, PartialOrd)]
+enum AcceptedUnits {
+    Year,
+    Month,
+    Week,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    Millisecond,
+    Microsecond,
+    Nanosecond,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reasonable_durations() {
+        let hours = parse_duration("3h").unwrap();
+        assert_eq!(hours, Duration::from_secs(3 * 3600));
+        let minutes = parse_duration("11m").unwrap();
+        assert_eq!(minutes, Duration::from_secs(11 * 60));
+        let seconds = parse_duration("55s").unwrap();
+        assert_eq!(seconds, Duration::from_secs(55));
+        let millis = parse_duration("100ms").unwrap();
+        assert_eq!(millis, Duration::from_millis(100));
+        let combined = "1h2m3s4ms";
+        let dur = parse_duration(combined).unwrap();
+        let expect = Duration::from_secs(3600)
+            + Duration::from_secs(120)
+            + Duration::from_secs(3)
+            + Duration::from_millis(4);
+        assert_eq!(dur, expect);
+    }
+
+    #[test]
+    fn parse_fractional_durations() {
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(
+            parse_duration("0.25h").unwrap(),
+            Duration::from_secs(15 * 60)
+        );
+        assert_eq!(
+            parse_duration("2.5ms").unwrap(),
+            Duration::from_micros(2500)
+        );
+        assert_eq!(parse_duration("0.5m").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("1.000000001s").unwrap(), Duration::new(1, 1));
+        assert_eq!(
+            parse_duration("1.50000000000000000000s").unwrap(),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(
+            parse_duration("1h0.5m1.25s").unwrap(),
+            Duration::from_secs(3600 + 30) + Duration::from_millis(1250)
+        );
+    }
+
+    #[test]
+    fn reject_imprecise_fractional_durations() {
+        assert!(parse_duration("1.0000000001s").is_err());
+        assert!(parse_duration("0.0000001ms").is_err());
+        assert!(parse_duration(".5s").is_err());
+        assert!(parse_duration("5.s").is_err());
+        assert!(parse_duration("1.2.3s").is_err());
+    }
+
+    #[test]
+    fn parse_small_and_large_units() {
+        assert_eq!(parse_duration("250us").unwrap(), Duration::from_micros(250));
+        assert_eq!(
+            parse_duration("250\u{b5}s").unwrap(),
+            Duration::from_micros(250)
+        );
+        assert_eq!(
+            parse_duration("250\u{3bc}s").unwrap(),
+            Duration::from_micros(250)
+        );
+        assert_eq!(parse_duration("75ns").unwrap(), Duration::from_nanos(75));
+        assert_eq!(
+            parse_duration("2d").unwrap(),
+            Duration::from_secs(2 * 86400)
+        );
+        assert_eq!(
+            parse_duration("1w").unwrap(),
+            Duration::from_secs(7 * 86400)
+        );
+        assert_eq!(
+            parse_duration("1w1d1h1m1s1ms1\u{b5}s1ns").unwrap(),
+            Duration::new(8 * 86400 + 3600 + 61, 1_001_001)
+        );
+        assert_eq!(parse_duration("1.5us").unwrap(), Duration::from_nanos(1500));
+        assert!(parse_duration("1.5ns").is_err());
+        assert!(parse_duration("1u").is_err());
+        assert!(parse_duration("1nm").is_err());
+    }
+
+    #[test]
+    fn parse_long_form_durations() {
+        let expect = Duration::from_secs(5400);
+        assert_eq!(parse_duration("1 hour 30 minutes").unwrap(), expect);
+        assert_eq!(parse_duration("1h 30m").unwrap(), expect);
+        assert_eq!(parse_duration("1hr30min").unwrap(), expect);
+        assert_eq!(parse_duration("  1 hours   30 mins ").unwrap(), expect);
+        assert_eq!(parse_duration("1.5 hours").unwrap(), expect);
+        assert_eq!(parse_duration("2 secs").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_duration("1 second").unwrap(), Duration::from_secs(1));
+        assert_eq!(parse_duration("5sec").unwrap(), Duration::from_secs(5));
+        assert_eq!(
+            parse_duration("10 millis").unwrap(),
+            Duration::from_millis(10)
+        );
+        assert_eq!(
+            parse_duration("10 milliseconds").unwrap(),
+            Duration::from_millis(10)
+        );
+        assert_eq!(parse_duration("3 usec").unwrap(), Duration::from_micros(3));
+        assert_eq!(parse_duration("3 nsec").unwrap(), Duration::from_nanos(3));
+        assert_eq!(
+            parse_duration("2 days").unwrap(),
+            Duration::from_secs(2 * 86400)
+        );
+        assert_eq!(
+            parse_duration("1 week").unwrap(),
+            Duration::from_secs(7 * 86400)
+        );
+        assert_eq!(
+            parse_duration("1M").unwrap(),
+            Duration::from_secs(2_630_016)
+        );
+        assert_eq!(
+            parse_duration("1y").unwrap(),
+            Duration::from_secs(31_557_600)
+        );
+        assert_eq!(
+            parse_duration("2years 2min 12us").unwrap(),
+            Duration::new(2 * 31_557_600 + 120, 12_000)
+        );
+    }
+
+    #[test]
+    fn reject_malformed_durations() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("   ").is_err());
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("1 hour 30").is_err());
+        assert!(parse_duration("hour").is_err());
+        assert!(parse_duration("1 hourz").is_err());
+        assert!(parse_duration("1 h, 30 m").is_err());
+        assert!(parse_duration("1 - 2s").is_err());
+        assert!(parse_duration("1z").is_err());
+    }
+
+    #[test]
+    fn parse_iso8601_durations() {
+        assert_eq!(
+            parse_duration("PT1H30M").unwrap(),
+            Duration::from_secs(5400)
+        );
+        assert_eq!(
+            parse_duration("PT2.5S").unwrap(),
+            Duration::from_millis(2500)
+        );
+        assert_eq!(
+            parse_duration("PT2,5S").unwrap(),
+            Duration::from_millis(2500)
+        );
+        assert_eq!(parse_duration("P1D").unwrap(), Duration::from_secs(86400));
+        assert_eq!(
+            parse_duration("P2W").unwrap(),
+            Duration::from_secs(14 * 86400)
+        );
+        assert_eq!(
+            parse_duration("P1DT1H1M1.001S").unwrap(),
+            Duration::new(86400 + 3661, 1_000_000)
+        );
+        assert_eq!(
+            parse_duration("PT0.000000001S").unwrap(),
+            Duration::new(0, 1)
+        );
+    }
+
+    #[test]
+    fn reject_malformed_iso8601_durations() {
+        assert!(parse_duration("P").is_err());
+        assert!(parse_duration("PT").is_err());
+        assert!(parse_duration("P1DT").is_err());
+        assert!(parse_duration("PT0S").is_err());
+        assert!(parse_duration("P1Y").is_err());
+        assert!(parse_duration("P1M").is_err());
+        assert!(parse_duration("P1H").is_err());
+        assert!(parse_duration("PT1D").is_err());
+        assert!(parse_duration("PT1M1H").is_err());
+        assert!(parse_duration("PT1H1H").is_err());
+        assert!(parse_duration("PT1").is_err());
+        assert!(parse_duration("PTH").is_err());
+        assert!(parse_duration("PT0.0000000001S").is_err());
+        assert!(parse_duration("PT1H 30M").is_err());
+        assert!(parse_duration("P18446744073709551615W").is_err());
+    }
+
+    #[test]
+    fn strict_durations() {
+        assert_eq!(
+            parse_duration_strict("1h2m3s4ms").unwrap(),
+            parse_duration("1h2m3s4ms").unwrap()
+        );
+        assert_eq!(
+            parse_duration_strict("1 hour 30 mins").unwrap(),
+            Duration::from_secs(5400)
+        );
+        assert_eq!(
+            parse_duration_strict("PT1H30M").unwrap(),
+            Duration::from_secs(5400)
+        );
+        assert!(parse_duration_strict("1h1h1h1h").is_err());
+        assert!(parse_duration_strict("1m1m").is_err());
+        assert!(parse_duration_strict("1h 1hour").is_err());
+        let err = parse_duration_strict("5ms2s1h5ms1m1s").unwrap_err();
+        assert!(
+            err.to_string().starts_with("'2s' in '5ms2s1h5ms1m1s'"),
+            "{err}"
+        );
+        let err = parse_duration_strict("1h 30 s 2 min").unwrap_err();
+        assert!(err.to_string().starts_with("'2 min' in"), "{err}");
+    }
+
+    #[test]
+    fn format_human_durations() {
+        for (dur, expect) in [
+            (Duration::ZERO, "0s"),
+            (Duration::from_millis(1500), "1.5s"),
+            (Duration::from_secs(3720), "1h2m"),
+            (Duration::from_secs(90 * 60), "1h30m"),
+            (Duration::from_secs(2 * 86_400 + 1), "2d1s"),
+            (Duration::from_micros(2500), "2.5ms"),
+            (Duration::from_micros(250), "250us"),
+            (Duration::from_nanos(1500), "1.5us"),
+            (Duration::from_nanos(7), "7ns"),
+            (Duration::new(61, 1), "1m1.000000001s"),
+            (Duration::from_millis(60_100), "1m100ms"),
+        ] {
+            assert_eq!(expect, HumanDuration(dur).to_string());
+        }
+    }
+
+    #[test]
+    fn formatted_durations_parse_back() {
+        for dur in [
+            Duration::from_millis(1500),
+            Duration::from_secs(3720),
+            Duration::new(86_400 * 400 + 3_599, 999_999_999),
+            Duration::from_nanos(1_001),
+            Duration::MAX,
+        ] {
+            let formatted = HumanDuration(dur).to_string();
+            assert_eq!(
+                dur,
+                parse_duration_strict(&formatted).unwrap(),
+                "{formatted}"
+            );
+        }
+    }
+
+    #[test]
+    fn error_kinds() {
+        for (dur, kind) in [
+            ("0s", ParseDurationErrorKind::Zero),
+            ("", ParseDurationErrorKind::Zero),
+            ("1.2.3s", ParseDurationErrorKind::InvalidNumber),
+            ("1", ParseDurationErrorKind::MissingUnit),
+            ("1z", ParseDurationErrorKind::UnknownUnit),
+            ("1.0000000001s", ParseDurationErrorKind::TooPrecise),
+            (
+                "18446744073709551616s",
+                ParseDurationErrorKind::InvalidNumber,
+            ),
+            ("18446744073709551615w", ParseDurationErrorKind::Overflow),
+            ("P1M", ParseDurationErrorKind::InvalidIso8601),
+        ] {
+            assert_eq!(kind, parse_duration(dur).unwrap_err().kind(), "{dur}");
+        }
+        assert_eq!(
+            ParseDurationErrorKind::UnitOrder,
+            parse_duration_strict("1m1m").unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn reject_overflowing_durations() {
+        assert!(parse_duration("18446744073709551615w").is_err());
+        assert!(parse_duration("18446744073709551615s1s").is_err());
+        assert!(parse_duration("18446744073709551616s").is_err());
+        assert_eq!(
+            parse_duration("18446744073709551615s").unwrap(),
+            Duration::from_secs(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn parse_unreasonable_additive_durations() {
+        let dur = "1h1h1h1h";
+        let dur = parse_duration(dur).unwrap();
+        assert_eq!(Duration::from_secs(3600 * 4), dur);
+        let dur = "1m1m1m";
+        let dur = parse_duration(dur).unwrap();
+        assert_eq!(Duration::from_secs(60 * 3), dur);
+        let dur = "1s1s";
+        let dur = parse_duration(dur).unwrap();
+        assert_eq!(Duration::from_secs(2), dur);
+        let dur = "1ms1ms1ms1ms1ms1ms1ms";
+        let dur = parse_duration(dur).unwrap();
+        assert_eq!(Duration::from_millis(7), dur);
+        let dur = "5ms2s1h5ms1m1s";
+        let dur = parse_duration(dur).unwrap();
+        assert_eq!(
+            Duration::from_millis(10) + Duration::from_secs(63) + Duration::from_secs(3600),
+            dur
+        );
+    }
+
+    #[test]
+    fn parse_unbounded_timeouts() {
+        assert_eq!(None, parse_timeout("none").unwrap());
+        assert_eq!(None, parse_timeout(" infinite ").unwrap());
+        assert_eq!(Some(Duration::from_secs(5)), parse_timeout("5s").unwrap());
+        assert!(parse_timeout("None").is_err());
+        assert!(parse_duration("none").is_err());
+    }
+}
@@ -0,0 +1,9 @@
+//! The duration grammar of the `duration` attribute, shared by the proc-macro,
+//! which parses durations at expansion time, and `tokio-timeout`, which parses them at runtime.
+#![warn(clippy::pedantic)]
+
+pub use crate::grammar::{parse_duration, parse_duration_strict, parse_timeout, HumanDuration};
+pub use crate::types::{ParseDurationError, ParseDurationErrorKind};
+
+mod grammar;
+mod types;
@@ -0,0 +1,49 @@
+use std::fmt::{Display, Formatter};
+
+/// The error returned when a string isn't a valid duration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDurationError {
+    kind: ParseDurationErrorKind,
+    message: String,
+}
+
+/// What made a duration invalid
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseDurationErrorKind {
+    /// The duration adds up to zero, or is empty
+    Zero,
+    /// A number couldn't be parsed
+    InvalidNumber,
+    /// A number isn't followed by a unit
+    MissingUnit,
+    /// A unit isn't recognised
+    UnknownUnit,
+    /// A value is more precise than nanoseconds
+    TooPrecise,
+    /// The duration doesn't fit in a `Duration`
+    Overflow,
+    /// A unit is repeated or comes after a smaller one, in strict mode
+    UnitOrder,
+    /// An ISO-8601 duration is malformed
+    InvalidIso8601,
+}
+
+impl ParseDurationError {
+    pub(crate) fn new(kind: ParseDurationErrorKind, message: String) -> Self {
+        Self { kind, message }
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> ParseDurationErrorKind {
+        self.kind
+    }
+}
+
+impl Display for ParseDurationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ParseDurationError {}
@@ -0,0 +1,99 @@
+use crate::tokens::{abs_path, braces, ident, internal_ident, let_binding, parens, punct};
+#[cfg(not(feature = "test"))]
+use proc_macro::{Ident, Literal, Punct, Spacing, TokenStream, TokenTree};
+#[cfg(feature = "test")]
+use proc_macro2::{Ident, Literal, Punct, Spacing, TokenStream, TokenTree};
+use std::time::Duration;
+
+/// Bounds for a duration evaluated at runtime
+pub(crate) struct Clamp {
+    pub(crate) min: Option<Duration>,
+    pub(crate) max: Option<Duration>,
+    pub(crate) on_clamp: Option<TokenStream>,
+}
+
+impl Clamp {
+    /// Shadows `duration` with the clamped duration, reporting it to `on_clamp` if it changed.
+    ///
+    /// ```text
+    /// let duration = {
+    ///     let clamped = ::core::cmp::Ord::clamp(duration, <min>, <max>);
+    ///     if clamped != duration {
+    ///         on_clamp(::tokio_timeout::DurationClamp { fn_name: "<fn_name>", .. });
+    ///     }
+    ///     clamped
+    /// };
+    /// ```
+    #[must_use]
+    pub(crate) fn rebind(self, fn_name: &str, duration: &Ident) -> TokenStream {
+        let mut bounded = TokenStream::from(TokenTree::Ident(duration.clone()));
+        let method = match (self.min, self.max) {
+            (Some(min), Some(max)) => {
+                bounded.extend([punct(',')]);
+                bounded.extend(crate::tokens::duration(min));
+                bounded.extend([punct(',')]);
+                bounded.extend(crate::tokens::duration(max));
+                "clamp"
+            }
+            (Some(min), None) => {
+                bounded.extend([punct(',')]);
+                bounded.extend(crate::tokens::duration(min));
+                "max"
+            }
+            (None, Some(max)) => {
+                bounded.extend([punct(',')]);
+                bounded.extend(crate::tokens::duration(max));
+                "min"
+            }
+            (None, None) => return TokenStream::new(),
+        };
+        let mut clamped_value = abs_path(&["core", "cmp", "Ord", method]);
+        clamped_value.extend([parens(bounded)]);
+        let Some(on_clamp) = self.on_clamp else {
+            return let_binding(duration, false, clamped_value);
+        };
+        let clamped = internal_ident("clamped");
+        let mut block = let_binding(&clamped, false, clamped_value);
+        block.extend([
+            ident("if"),
+            TokenTree::Ident(clamped.clone()),
+            TokenTree::Punct(Punct::new('!', Spacing::Joint)),
+            TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+            TokenTree::Ident(duration.clone()),
+            braces(report(on_clamp, fn_name, duration, &clamped)),
+            TokenTree::Ident(clamped),
+        ]);
+        let_binding(duration, false, TokenStream::from(braces(block)))
+    }
+}
+
+/// `on_clamp(::tokio_timeout::DurationClamp { fn_name: "<fn_name>", .. });`
+fn report(on_clamp: TokenStream, fn_name: &str, duration: &Ident, clamped: &Ident) -> TokenStream {
+    let mut module_path = abs_path(&["core", "module_path"]);
+    module_path.extend([punct('!'), parens(TokenStream::new())]);
+    let mut fields = TokenStream::new();
+    for (name, value) in [
+        (
+            "fn_name",
+            TokenStream::from(TokenTree::Literal(Literal::string(fn_name))),
+        ),
+        ("module_path", module_path),
+        (
+            "configured",
+            TokenStream::from(TokenTree::Ident(duration.clone())),
+        ),
+        (
+            "clamped",
+            TokenStream::from(TokenTree::Ident(clamped.clone())),
+        ),
+    ] {
+        fields.extend([ident(name), punct(':')]);
+        fields.extend(value);
+        fields.extend([punct(',')]);
+    }
+    let mut event = abs_path(&["tokio_timeout", "DurationClamp"]);
+    event.extend([braces(fields)]);
+    let mut ts = crate::tokens::callee(on_clamp);
+    ts.extend([parens(event), punct(';')]);
+    ts
+}
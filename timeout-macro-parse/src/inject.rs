@@ -1,11 +1,12 @@
 use crate::Error;
 #[cfg(not(feature = "test"))]
-use proc_macro::{Delimiter, Span, TokenStream, TokenTree};
+use proc_macro::{Delimiter, Ident, Spacing, Span, TokenStream, TokenTree};
 #[cfg(feature = "test")]
-use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use proc_macro2::{Delimiter, Ident, Spacing, Span, TokenStream, TokenTree};
 
 pub(crate) trait Injector {
-    fn inject(self, fn_name: &str, inner_code: TokenStream) -> TokenStream;
+    /// `by_value` are the arguments the function takes by value, see [`by_value_args`]
+    fn inject(self, fn_name: &str, by_value: &[Ident], inner_code: TokenStream) -> TokenStream;
 }
 
 pub(crate) fn try_inject(
@@ -14,22 +15,37 @@ pub(crate) fn try_inject(
 ) -> crate::Result<TokenStream> {
     let mut it = source.into_iter();
     let mut pre = TokenStream::new();
-    let (fn_name, inner_body) = extract_inner_body(&mut pre, &mut it)?;
-    let res = injector.inject(&fn_name, inner_body);
+    let (fn_name, params, inner_body) = extract_inner_body(&mut pre, &mut it)?;
+    let res = injector.inject(&fn_name, &by_value_args(params), inner_body);
     pre.extend([res]);
     Ok(pre)
 }
 
+/// The function's name, parameters and body
 fn extract_inner_body(
     pre: &mut TokenStream,
     source: &mut impl Iterator<Item = TokenTree>,
-) -> crate::Result<(String, TokenStream)> {
+) -> crate::Result<(String, TokenStream, TokenStream)> {
     let mut seen_async = false;
     let mut seen_fn_decl = false;
     let mut fn_name = None;
+    let mut params = None;
+    let mut generics = AngleDepth::default();
     let mut last = None;
     let mut peek = source.peekable();
     while let Some(token) = peek.next() {
+        if fn_name.is_some() && params.is_none() {
+            // The first parenthesis after the name and its generics, which may contain
+            // parenthesis of their own, such as `F: Fn(u32)`
+            match &token {
+                TokenTree::Group(g) if g.delimiter() == Delimiter::Parenthesis => {
+                    if generics.depth == 0 {
+                        params = Some(g.stream());
+                    }
+                }
+                t => generics.track(t),
+            }
+        }
         match &token {
             TokenTree::Ident(id) => {
                 let id = id.to_string();
@@ -83,5 +99,85 @@ fn extract_inner_body(
             "'timeout' macro unable to find fn name",
         ));
     };
-    Ok((fn_name, group.stream()))
+    Ok((fn_name, params.unwrap_or_default(), group.stream()))
+}
+
+/// The arguments bound by value to a plain name, `name: Type` or `mut name: Type` where `Type`
+/// isn't a reference. Retried functions get a fresh clone of these for each attempt.
+/// `self` and destructuring patterns are left out.
+fn by_value_args(params: TokenStream) -> Vec<Ident> {
+    let mut args = Vec::new();
+    let mut param = Vec::new();
+    let mut depth = AngleDepth::default();
+    for token in params {
+        if let TokenTree::Punct(p) = &token {
+            if p.as_char() == ',' && depth.depth == 0 {
+                args.extend(by_value_arg(&std::mem::take(&mut param)));
+                continue;
+            }
+        }
+        depth.track(&token);
+        param.push(token);
+    }
+    args.extend(by_value_arg(&param));
+    args
+}
+
+fn by_value_arg(param: &[TokenTree]) -> Option<Ident> {
+    let mut it = param.iter().peekable();
+    // Attributes, `#[..]`
+    while let Some(TokenTree::Punct(p)) = it.peek() {
+        if p.as_char() != '#' {
+            break;
+        }
+        it.next();
+        it.next();
+    }
+    let TokenTree::Ident(first) = it.next()? else {
+        return None;
+    };
+    let name = if matches!(first.to_string().as_str(), "mut") {
+        let TokenTree::Ident(after_mut) = it.next()? else {
+            return None;
+        };
+        after_mut
+    } else {
+        first
+    };
+    if matches!(name.to_string().as_str(), "self" | "_") {
+        return None;
+    }
+    let TokenTree::Punct(colon) = it.next()? else {
+        return None;
+    };
+    if colon.as_char() != ':' || colon.spacing() != Spacing::Alone {
+        return None;
+    }
+    match it.next()? {
+        TokenTree::Punct(p) if p.as_char() == '&' => None,
+        _ => Some(name.clone()),
+    }
+}
+
+/// Nesting of `<..>` outside of groups, which aren't groups themselves.
+/// The `>` of a `->` doesn't close anything.
+#[derive(Default)]
+struct AngleDepth {
+    depth: usize,
+    after_dash: bool,
+}
+
+impl AngleDepth {
+    fn track(&mut self, token: &TokenTree) {
+        let TokenTree::Punct(p) = token else {
+            self.after_dash = false;
+            return;
+        };
+        match p.as_char() {
+            '<' => self.depth += 1,
+            '>' if !self.after_dash => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
+        self.after_dash = p.as_char() == '-' && p.spacing() == Spacing::Joint;
+    }
 }
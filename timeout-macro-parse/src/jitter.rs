@@ -0,0 +1,60 @@
+use crate::tokens::{abs_path, let_binding, parens, punct};
+#[cfg(not(feature = "test"))]
+use proc_macro::{Ident, Literal, TokenStream, TokenTree};
+#[cfg(feature = "test")]
+use proc_macro2::{Ident, Literal, TokenStream, TokenTree};
+use std::time::Duration;
+use timeout_duration::HumanDuration;
+
+/// How far, in either direction, each call's timeout may stray from `duration`
+pub(crate) enum Jitter {
+    Fixed(Duration),
+    /// In percent of `duration`
+    Percent(u32),
+}
+
+impl Jitter {
+    /// Appended to the duration in messages, such as `1s ±100ms`
+    #[must_use]
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            Jitter::Fixed(d) => format!(" \u{b1}{}", HumanDuration(*d)),
+            Jitter::Percent(p) => format!(" \u{b1}{p}%"),
+        }
+    }
+
+    /// `let duration = ::tokio_timeout::__private::jitter(duration, <jitter>);`
+    #[must_use]
+    pub(crate) fn rebind(&self, duration: &Ident) -> TokenStream {
+        let band = match self {
+            Jitter::Fixed(d) => {
+                let mut ts = abs_path(&["tokio_timeout", "__private", "Jitter", "Fixed"]);
+                ts.extend([parens(crate::tokens::duration(*d))]);
+                ts
+            }
+            Jitter::Percent(p) => {
+                let mut ts = abs_path(&["tokio_timeout", "__private", "Jitter", "Percent"]);
+                ts.extend([parens(TokenStream::from(TokenTree::Literal(
+                    Literal::u32_unsuffixed(*p),
+                )))]);
+                ts
+            }
+        };
+        let mut args = TokenStream::from_iter([TokenTree::Ident(duration.clone()), punct(',')]);
+        args.extend(band);
+        let mut call = abs_path(&["tokio_timeout", "__private", "jitter"]);
+        call.extend([parens(args)]);
+        let_binding(duration, false, call)
+    }
+}
+
+/// Parses `"<n>%"` as a percentage in `1..100`, `None` if it isn't a percentage at all
+pub(crate) fn parse_percent(jitter: &str) -> Option<Result<u32, String>> {
+    let percent = jitter.strip_suffix('%')?;
+    Some(match percent.trim().parse::<u32>() {
+        Ok(p) if (1..100).contains(&p) => Ok(p),
+        _ => Err(format!(
+            "Expected 'jitter' percentage to be a whole number from 1 to 99, got '{jitter}'"
+        )),
+    })
+}
@@ -1,19 +1,29 @@
 #![warn(clippy::pedantic)]
 extern crate proc_macro;
 
+use crate::clamp::Clamp;
 use crate::compile_error::to_compile_error;
 use crate::inject::{try_inject, Injector};
-use crate::parse_attr::{parse_attr, ValidOpts};
+use crate::jitter::Jitter;
+use crate::parse_attr::{parse_attr, OnError, ParsedDuration, ValidOpts};
+use crate::runtime::Runtime;
 #[cfg(not(feature = "test"))]
 use proc_macro::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
 #[cfg(feature = "test")]
 use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
 use std::fmt::Display;
+use std::time::Duration;
 
+mod clamp;
 mod compile_error;
 mod inject;
+mod jitter;
+mod message;
 mod parse_attr;
-mod parse_duration;
+mod retry;
+mod runtime;
+mod slow;
+mod tokens;
 
 struct TokioTimeoutInjector(ValidOpts);
 
@@ -49,55 +59,300 @@ impl Error {
 pub(crate) type Result<T> = core::result::Result<T, Error>;
 
 impl Injector for TokioTimeoutInjector {
-    fn inject(self, fn_name: &str, inner_code: TokenStream) -> TokenStream {
-        let err_disp = self.0.duration.to_error_display(fn_name);
-        let on_timeout = self.0.on_error.into_token_stream(&err_disp);
-        let dur = self.0.duration.into_token_stream();
-        let mut inner = TokenStream::new();
+    fn inject(self, fn_name: &str, by_value: &[Ident], inner_code: TokenStream) -> TokenStream {
+        let opts = self.0;
+        let err_disp = opts.message(fn_name);
+        let known = opts.known_duration();
+        let duration = crate::tokens::internal_ident("duration");
+        let started = crate::tokens::internal_ident("started");
+        let elapsed = opts.runtime.elapsed(&started);
+        let attempt = crate::tokens::internal_ident("attempt");
+        let error = crate::tokens::internal_ident("e");
+        let message = match &opts.retry {
+            Some(retry) => retry.message(&err_disp, &attempt),
+            None => err_disp.to_token_stream(""),
+        };
+        let measure_elapsed = matches!(opts.on_error, OnError::Context(_))
+            || opts.retry.as_ref().is_some_and(|r| r.total.is_some());
+        let adjusted = opts.jitter.is_some() || opts.clamp.is_some();
+        let effective_message = opts
+            .formats_effective_message()
+            .then(|| crate::tokens::internal_ident("message"));
+        let handler_message = match &effective_message {
+            Some(ident) => TokenStream::from_iter([
+                crate::tokens::punct('&'),
+                crate::tokens::punct('*'),
+                TokenTree::Ident(ident.clone()),
+            ]),
+            None => message.clone(),
+        };
+        let on_error_message = if matches!(opts.on_error, OnError::Result(_)) {
+            &handler_message
+        } else {
+            &message
+        };
+        let mut on_timeout = opts.on_error.into_token_stream(
+            fn_name,
+            on_error_message,
+            &duration,
+            &error,
+            &elapsed,
+            adjusted,
+        );
+        if let Some(log) = opts.log {
+            on_timeout = log_then(log, handler_message, on_timeout);
+        }
+        if let Some(ident) = effective_message {
+            on_timeout = effective_message_block(&ident, message, &duration, on_timeout);
+        }
+        let timeout = crate::tokens::internal_ident("timeout");
+        // A total retry budget still bounds each attempt when the duration is unbounded
+        let optional = (opts.duration.is_optional()
+            && !opts.retry.as_ref().is_some_and(|r| r.total.is_some()))
+        .then_some(&timeout);
+        let mut inner = bind_duration(
+            fn_name,
+            opts.duration,
+            opts.clamp,
+            opts.jitter,
+            &duration,
+            &timeout,
+        );
         let span = Span::call_site();
-        let mut timeout_args = TokenStream::new();
-        timeout_args.extend(dur);
-        timeout_args.extend([
-            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
-            TokenTree::Ident(Ident::new("async", span)),
-            TokenTree::Group(Group::new(Delimiter::Brace, inner_code)),
-        ]);
-        let mut match_body = TokenStream::new();
-        let mut value_group = TokenStream::new();
-        value_group.extend([TokenTree::Ident(Ident::new("v", span))]);
-        let mut err_group = TokenStream::new();
-        err_group.extend([TokenTree::Ident(Ident::new("e", span))]);
-        match_body.extend([
-            TokenTree::Ident(Ident::new("Ok", span)),
-            TokenTree::Group(Group::new(Delimiter::Parenthesis, value_group)),
-            TokenTree::Punct(Punct::new('=', Spacing::Joint)),
-            TokenTree::Punct(Punct::new('>', Spacing::Alone)),
-            TokenTree::Ident(Ident::new("v", span)),
-            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
-            TokenTree::Ident(Ident::new("Err", span)),
-            TokenTree::Group(Group::new(Delimiter::Parenthesis, err_group)),
-            TokenTree::Punct(Punct::new('=', Spacing::Joint)),
-            TokenTree::Punct(Punct::new('>', Spacing::Alone)),
-        ]);
-        match_body.extend(on_timeout);
-        inner.extend([
-            TokenTree::Ident(Ident::new("match", span)),
-            TokenTree::Ident(Ident::new("tokio", span)),
-            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
-            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
-            TokenTree::Ident(Ident::new("time", span)),
-            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
-            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
-            TokenTree::Ident(Ident::new("timeout", span)),
-            TokenTree::Group(Group::new(Delimiter::Parenthesis, timeout_args)),
-            TokenTree::Punct(Punct::new('.', Spacing::Alone)),
-            TokenTree::Ident(Ident::new("await", span)),
-            TokenTree::Group(Group::new(Delimiter::Brace, match_body)),
-        ]);
+        if measure_elapsed {
+            // `let started = ::tokio::time::Instant::now();`
+            inner.extend(crate::tokens::let_binding(
+                &started,
+                false,
+                opts.runtime.now(),
+            ));
+        }
+        let attempt_duration = match &opts.retry {
+            Some(retry) => retry.attempt_duration(&duration, &elapsed),
+            None => TokenStream::from(TokenTree::Ident(duration)),
+        };
+        let body = if let Some(slow) = opts.slow {
+            slow.wrap(fn_name, inner_code, &opts.runtime)
+        } else {
+            TokenStream::from_iter([
+                TokenTree::Ident(Ident::new("async", span)),
+                TokenTree::Group(Group::new(Delimiter::Brace, inner_code)),
+            ])
+        };
+        let timeout_call = timeout_call(&opts.runtime, attempt_duration, known, body, optional);
+        let match_body = match_arms(&error, on_timeout);
+        if let Some(retry) = opts.retry {
+            // Re-runs the body with a fresh timeout per attempt, then matches on the last result
+            let result = crate::tokens::internal_ident("result");
+            inner.extend(retry.into_loop(
+                &attempt,
+                &result,
+                &elapsed,
+                by_value,
+                timeout_call,
+                &opts.runtime,
+            ));
+            inner.extend([
+                TokenTree::Ident(Ident::new("match", span)),
+                TokenTree::Ident(result),
+            ]);
+        } else {
+            inner.extend([TokenTree::Ident(Ident::new("match", span))]);
+            inner.extend(timeout_call);
+        }
+        inner.extend([TokenTree::Group(Group::new(Delimiter::Brace, match_body))]);
         TokenStream::from(TokenTree::Group(Group::new(Delimiter::Brace, inner)))
     }
 }
 
+/// `{ log("..."); <on_timeout> }`
+fn log_then(log: TokenStream, message: TokenStream, on_timeout: TokenStream) -> TokenStream {
+    let mut log_call = crate::tokens::callee(log);
+    log_call.extend([
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, message)),
+        crate::tokens::punct(';'),
+    ]);
+    log_call.extend(on_timeout);
+    TokenStream::from(TokenTree::Group(Group::new(Delimiter::Brace, log_call)))
+}
+
+/// Binds the message with the effective timeout for `log` and `on_error` handlers,
+/// which get it as `&*message`
+///
+/// ```text
+/// {
+///     let message = ::tokio_timeout::__private::effective_message(<message>, duration);
+///     <on_timeout>
+/// }
+/// ```
+fn effective_message_block(
+    ident: &Ident,
+    message: TokenStream,
+    duration: &Ident,
+    on_timeout: TokenStream,
+) -> TokenStream {
+    let mut args = message;
+    args.extend([
+        crate::tokens::punct(','),
+        TokenTree::Ident(duration.clone()),
+    ]);
+    let mut call = crate::tokens::abs_path(&["tokio_timeout", "__private", "effective_message"]);
+    call.extend([crate::tokens::parens(args)]);
+    let mut ts = crate::tokens::let_binding(ident, false, call);
+    ts.extend(on_timeout);
+    TokenStream::from(crate::tokens::braces(ts))
+}
+
+/// `::core::result::Result::Ok(v) => v, ::core::result::Result::Err(e) => <on_timeout>`,
+/// fully qualified so that user types or glob imports named `Ok` and `Err` can't interfere
+fn match_arms(error: &Ident, on_timeout: TokenStream) -> TokenStream {
+    let value = crate::tokens::internal_ident("v");
+    let mut match_body = crate::tokens::abs_path(&["core", "result", "Result", "Ok"]);
+    match_body.extend([
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenStream::from(TokenTree::Ident(value.clone())),
+        )),
+        TokenTree::Punct(Punct::new('=', Spacing::Joint)),
+        TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+        TokenTree::Ident(value),
+        TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+    ]);
+    match_body.extend(crate::tokens::abs_path(&[
+        "core", "result", "Result", "Err",
+    ]));
+    match_body.extend([
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenStream::from(TokenTree::Ident(error.clone())),
+        )),
+        TokenTree::Punct(Punct::new('=', Spacing::Joint)),
+        TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+    ]);
+    match_body.extend(on_timeout);
+    match_body
+}
+
+/// `let duration = <dur>;`, evaluated once before running the body,
+/// then shadowed by the jittered and clamped durations, if configured.
+///
+/// A duration that may be unbounded is bound to `timeout` as an `Option<Duration>` instead,
+/// with `duration` falling back to `Duration::MAX`, never used to time out.
+///
+/// ```text
+/// let timeout = <dur>;
+/// let timeout = ::core::option::Option::map(timeout, |duration: ::core::time::Duration| {
+///     <jitter and clamp>
+///     duration
+/// });
+/// let duration = ::core::option::Option::unwrap_or(timeout, ::core::time::Duration::MAX);
+/// ```
+fn bind_duration(
+    fn_name: &str,
+    dur: ParsedDuration,
+    clamp: Option<Clamp>,
+    jitter: Option<Jitter>,
+    duration: &Ident,
+    timeout: &Ident,
+) -> TokenStream {
+    let mut adjust = TokenStream::new();
+    // Clamped last, so that the jittered duration can't stray outside of `min` and `max`
+    if let Some(jitter) = jitter {
+        adjust.extend(jitter.rebind(duration));
+    }
+    if let Some(clamp) = clamp {
+        adjust.extend(clamp.rebind(fn_name, duration));
+    }
+    if !dur.is_optional() {
+        let mut ts = crate::tokens::let_binding(duration, false, dur.into_token_stream());
+        ts.extend(adjust);
+        return ts;
+    }
+    let mut ts = crate::tokens::let_binding(timeout, false, dur.into_token_stream());
+    if !adjust.is_empty() {
+        let mut closure = TokenStream::from_iter([
+            crate::tokens::punct('|'),
+            TokenTree::Ident(duration.clone()),
+            crate::tokens::punct(':'),
+        ]);
+        closure.extend(crate::tokens::abs_path(&["core", "time", "Duration"]));
+        adjust.extend([TokenTree::Ident(duration.clone())]);
+        closure.extend([crate::tokens::punct('|'), crate::tokens::braces(adjust)]);
+        let mut args =
+            TokenStream::from_iter([TokenTree::Ident(timeout.clone()), crate::tokens::punct(',')]);
+        args.extend(closure);
+        let mut map = crate::tokens::abs_path(&["core", "option", "Option", "map"]);
+        map.extend([crate::tokens::parens(args)]);
+        ts.extend(crate::tokens::let_binding(timeout, false, map));
+    }
+    let mut args =
+        TokenStream::from_iter([TokenTree::Ident(timeout.clone()), crate::tokens::punct(',')]);
+    args.extend(crate::tokens::abs_path(&[
+        "core", "time", "Duration", "MAX",
+    ]));
+    let mut unwrap_or = crate::tokens::abs_path(&["core", "option", "Option", "unwrap_or"]);
+    unwrap_or.extend([crate::tokens::parens(args)]);
+    ts.extend(crate::tokens::let_binding(duration, false, unwrap_or));
+    ts
+}
+
+/// `::tokio::time::timeout(<attempt_duration>, <body>).await`, or the runtime's equivalent
+///
+/// When the duration may be unbounded, the timer is skipped altogether if it is:
+///
+/// ```text
+/// {
+///     let fut = <body>;
+///     if ::core::option::Option::is_some(&timeout) {
+///         ::tokio::time::timeout(<attempt_duration>, fut).await
+///     } else {
+///         ::core::result::Result::Ok(fut.await)
+///     }
+/// }
+/// ```
+fn timeout_call(
+    runtime: &Runtime,
+    attempt_duration: TokenStream,
+    known: Option<Duration>,
+    body: TokenStream,
+    timeout: Option<&Ident>,
+) -> TokenStream {
+    let fut = crate::tokens::internal_ident("fut");
+    let call = if timeout.is_some() {
+        runtime.timeout(
+            attempt_duration,
+            known,
+            TokenStream::from(TokenTree::Ident(fut.clone())),
+        )
+    } else {
+        runtime.timeout(attempt_duration, known, body.clone())
+    };
+    let Some(timeout) = timeout else {
+        return call;
+    };
+    let mut ts = crate::tokens::let_binding(&fut, false, body);
+    ts.extend([crate::tokens::ident("if")]);
+    ts.extend(crate::tokens::abs_path(&[
+        "core", "option", "Option", "is_some",
+    ]));
+    ts.extend([
+        crate::tokens::parens(TokenStream::from_iter([
+            crate::tokens::punct('&'),
+            TokenTree::Ident(timeout.clone()),
+        ])),
+        crate::tokens::braces(call),
+        crate::tokens::ident("else"),
+    ]);
+    let mut unbounded = crate::tokens::abs_path(&["core", "result", "Result", "Ok"]);
+    unbounded.extend([crate::tokens::parens(TokenStream::from_iter([
+        TokenTree::Ident(fut),
+        crate::tokens::punct('.'),
+        crate::tokens::ident("await"),
+    ]))]);
+    ts.extend([crate::tokens::braces(unbounded)]);
+    TokenStream::from(crate::tokens::braces(ts))
+}
+
 #[must_use]
 pub fn tokio_timeout(attr: TokenStream, item: TokenStream) -> TokenStream {
     let validated = match parse_attr(attr) {
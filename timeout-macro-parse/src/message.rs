@@ -0,0 +1,194 @@
+//! The timeout message, either the default one or a `message = "..."` template
+//! with `{fn}`, `{module}`, `{duration}` and `{location}` placeholders.
+use crate::tokens::{abs_path, parens, punct};
+#[cfg(not(feature = "test"))]
+use proc_macro::{Literal, TokenStream, TokenTree};
+#[cfg(feature = "test")]
+use proc_macro2::{Literal, TokenStream, TokenTree};
+
+#[derive(Clone)]
+enum Part {
+    Text(String),
+    Fn,
+    Module,
+    Duration,
+    Location,
+}
+
+/// A message template validated when parsing the attribute
+pub(crate) struct MessageTemplate(Vec<Part>);
+
+impl MessageTemplate {
+    /// Parses a template, `{{` and `}}` are literal braces.
+    pub(crate) fn parse(template: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('{') if name.is_empty() => {
+                                text.push('{');
+                                break;
+                            }
+                            Some('}') => {
+                                let part = match name.as_str() {
+                                    "fn" => Part::Fn,
+                                    "module" => Part::Module,
+                                    "duration" => Part::Duration,
+                                    "location" => Part::Location,
+                                    unk => {
+                                        return Err(format!("Unknown placeholder '{{{unk}}}' in 'message', expected one of {{fn}}, {{module}}, {{duration}} or {{location}}"));
+                                    }
+                                };
+                                if !text.is_empty() {
+                                    parts.push(Part::Text(std::mem::take(&mut text)));
+                                }
+                                parts.push(part);
+                                break;
+                            }
+                            Some(c) => name.push(c),
+                            None => {
+                                return Err(
+                                    "Unclosed '{' in 'message', use '{{' for a literal brace"
+                                        .to_string(),
+                                );
+                            }
+                        }
+                    }
+                }
+                '}' => {
+                    if chars.next() != Some('}') {
+                        return Err(
+                            "Unmatched '}' in 'message', use '}}' for a literal brace".to_string()
+                        );
+                    }
+                    text.push('}');
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self(parts))
+    }
+
+    /// Fills in the placeholders known at expansion time
+    #[must_use]
+    pub(crate) fn render(&self, fn_name: &str, duration: &str) -> Message {
+        Message(
+            self.0
+                .iter()
+                .map(|part| match part {
+                    Part::Fn => Part::Text(fn_name.to_string()),
+                    Part::Duration => Part::Text(duration.to_string()),
+                    p => p.clone(),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// A message with only the placeholders that are filled in by the compiler left,
+/// `{module}` and `{location}`
+pub(crate) struct Message(Vec<Part>);
+
+impl Message {
+    #[must_use]
+    pub(crate) fn text(text: String) -> Self {
+        Self(vec![Part::Text(text)])
+    }
+
+    /// An expression evaluating to the `&'static str` message followed by `suffix`,
+    /// a plain literal, or `::core::concat!(..)` if the module or location is included.
+    #[must_use]
+    pub(crate) fn to_token_stream(&self, suffix: &str) -> TokenStream {
+        let mut text = String::new();
+        let mut args = TokenStream::new();
+        let mut is_literal = true;
+        for part in &self.0 {
+            match part {
+                Part::Text(t) => text.push_str(t),
+                Part::Module => {
+                    push_text(&mut args, &mut text);
+                    args.extend(builtin_macro("module_path"));
+                    args.extend([punct(',')]);
+                    is_literal = false;
+                }
+                Part::Location => {
+                    push_text(&mut args, &mut text);
+                    // `<file>:<line>`
+                    args.extend(builtin_macro("file"));
+                    args.extend([punct(','), TokenTree::Literal(Literal::string(":"))]);
+                    args.extend([punct(',')]);
+                    args.extend(builtin_macro("line"));
+                    args.extend([punct(',')]);
+                    is_literal = false;
+                }
+                // Rendered before getting here
+                Part::Fn | Part::Duration => {}
+            }
+        }
+        text.push_str(suffix);
+        if is_literal {
+            return TokenStream::from(TokenTree::Literal(Literal::string(&text)));
+        }
+        push_text(&mut args, &mut text);
+        let mut ts = abs_path(&["core", "concat"]);
+        ts.extend([punct('!'), parens(args)]);
+        ts
+    }
+}
+
+/// Pushes the text gathered so far, if any, as an argument to `concat!`
+fn push_text(args: &mut TokenStream, text: &mut String) {
+    if !text.is_empty() {
+        args.extend([
+            TokenTree::Literal(Literal::string(&std::mem::take(text))),
+            punct(','),
+        ]);
+    }
+}
+
+/// `::core::<name>!()`
+fn builtin_macro(name: &str) -> TokenStream {
+    let mut ts = abs_path(&["core", name]);
+    ts.extend([punct('!'), parens(TokenStream::new())]);
+    ts
+}
+
+/// The value of a string literal, or `None` if it isn't one.
+/// Handles the common escapes and raw strings.
+pub(crate) fn string_value(lit: &Literal) -> Option<String> {
+    let repr = lit.to_string();
+    if let Some(raw) = repr.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let inner = raw
+            .get(hashes..raw.len().checked_sub(hashes)?)?
+            .strip_prefix('"')?
+            .strip_suffix('"')?;
+        return Some(inner.to_string());
+    }
+    let inner = repr.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            value.push(ch);
+            continue;
+        }
+        value.push(match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            c @ ('\\' | '"' | '\'') => c,
+            _ => return None,
+        });
+    }
+    Some(value)
+}
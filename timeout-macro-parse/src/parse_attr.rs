@@ -1,149 +1,653 @@
+use crate::clamp::Clamp;
+use crate::jitter::Jitter;
+use crate::message::{Message, MessageTemplate};
+use crate::retry::{Backoff, BackoffStrategy, Retry};
+use crate::runtime::Runtime;
+use crate::slow::SlowWarning;
 use crate::Error;
 #[cfg(not(feature = "test"))]
 use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
 #[cfg(feature = "test")]
 use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
 use std::time::Duration;
+use timeout_duration::HumanDuration;
 
 pub(crate) fn parse_attr(attr: TokenStream) -> crate::Result<ValidOpts> {
     let mut opts = Opts::default();
     let mut it = attr.into_iter();
     while take_next(&mut opts, &mut it)? {}
+    if opts.strict.is_some() {
+        // Validated after all attributes are read, since `strict` may come last
+        for lit in &opts.duration_literals {
+            timeout_duration::parse_duration_strict(lit.to_string().trim_matches('"'))
+                .map_err(|e| Error::with_span(lit.span(), e))?;
+        }
+    }
+    let duration = opts
+        .duration
+        .ok_or_else(|| Error::missing_span("Missing 'duration' attribute".to_string()))?;
+    let duration = match (opts.duration_env, duration) {
+        (Some((var, _span)), ParsedDuration::Duration(default)) => ParsedDuration::Env {
+            var,
+            default: Some(default),
+        },
+        (Some((var, _span)), ParsedDuration::Unbounded) => {
+            ParsedDuration::Env { var, default: None }
+        }
+        (Some((_var, span)), ParsedDuration::Ref(_)) => {
+            return Err(Error::with_span(
+                span,
+                "'duration_env' requires 'duration' to be a literal, used when the variable is unset or invalid",
+            ));
+        }
+        (_, duration) => duration,
+    };
+    if matches!(duration, ParsedDuration::Unbounded)
+        && (opts.jitter.is_some() || opts.min.is_some() || opts.max.is_some())
+    {
+        return Err(Error::missing_span(
+            "'jitter', 'min' and 'max' can't be used with an unbounded 'duration'".to_string(),
+        ));
+    }
+    let slow = validate_slow(opts.warn_after, opts.on_slow, &duration)?;
+    let clamp = validate_clamp(opts.min, opts.max, opts.on_clamp, &duration)?;
+    if let (Some(Jitter::Fixed(jitter)), ParsedDuration::Duration(d)) = (&opts.jitter, &duration) {
+        if jitter >= d {
+            return Err(Error::missing_span(
+                "'jitter' has to be shorter than 'duration'".to_string(),
+            ));
+        }
+    }
+    let runtime = opts
+        .runtime
+        .unwrap_or(Runtime::Tokio(None))
+        .with_crate(opts.tokio_crate)
+        .map_err(Error::missing_span)?;
+    if let Runtime::Embassy = runtime {
+        validate_no_std(&duration, opts.jitter.as_ref(), slow.as_ref())?;
+    }
     Ok(ValidOpts {
-        duration: opts
-            .duration
-            .ok_or_else(|| Error::missing_span("Missing 'duration' attribute".to_string()))?,
-        on_error: opts
-            .on_error
-            .ok_or_else(|| Error::missing_span("Missing 'on_error'".to_string()))?,
+        duration,
+        on_error: opts.on_error.ok_or_else(|| {
+            Error::missing_span(
+                "Missing one of 'on_error', 'on_error_ctx' or 'fallback'".to_string(),
+            )
+        })?,
+        log: opts.log,
+        message: opts.message,
+        jitter: opts.jitter,
+        clamp,
+        retry: validate_retry(
+            opts.retries,
+            opts.backoff,
+            opts.backoff_strategy,
+            opts.total,
+        )?,
+        slow,
+        runtime,
     })
 }
 
+fn validate_slow(
+    warn_after: Option<Duration>,
+    on_slow: Option<TokenStream>,
+    duration: &ParsedDuration,
+) -> crate::Result<Option<SlowWarning>> {
+    let Some(after) = warn_after else {
+        if on_slow.is_some() {
+            return Err(Error::missing_span(
+                "'on_slow' can only be used together with 'warn_after'".to_string(),
+            ));
+        }
+        return Ok(None);
+    };
+    if let ParsedDuration::Duration(d) = duration {
+        if after >= *d {
+            return Err(Error::missing_span(
+                "'warn_after' has to be shorter than 'duration'".to_string(),
+            ));
+        }
+    }
+    Ok(Some(SlowWarning { after, on_slow }))
+}
+
+/// The embassy runtime has no `std`, for reading the environment, randomising or printing
+fn validate_no_std(
+    duration: &ParsedDuration,
+    jitter: Option<&Jitter>,
+    slow: Option<&SlowWarning>,
+) -> crate::Result<()> {
+    let needs_std = if let ParsedDuration::Env { .. } = duration {
+        Some("'duration_env'")
+    } else if jitter.is_some() {
+        Some("'jitter'")
+    } else if slow.is_some_and(|slow| slow.on_slow.is_none()) {
+        Some("'warn_after' without 'on_slow'")
+    } else {
+        None
+    };
+    match needs_std {
+        Some(attr) => Err(Error::missing_span(format!(
+            "{attr} needs std, which isn't available with the embassy runtime"
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn validate_clamp(
+    min: Option<Duration>,
+    max: Option<Duration>,
+    on_clamp: Option<TokenStream>,
+    duration: &ParsedDuration,
+) -> crate::Result<Option<Clamp>> {
+    if min.is_none() && max.is_none() {
+        if on_clamp.is_some() {
+            return Err(Error::missing_span(
+                "'on_clamp' can only be used together with 'min' or 'max'".to_string(),
+            ));
+        }
+        return Ok(None);
+    }
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            return Err(Error::missing_span(
+                "'min' has to be at most 'max'".to_string(),
+            ));
+        }
+    }
+    if let ParsedDuration::Duration(d) = duration {
+        if min.is_some_and(|min| *d < min) || max.is_some_and(|max| *d > max) {
+            return Err(Error::missing_span(
+                "'duration' is outside of 'min' and 'max'".to_string(),
+            ));
+        }
+    }
+    Ok(Some(Clamp { min, max, on_clamp }))
+}
+
+fn validate_retry(
+    retries: Option<u32>,
+    backoff: Option<Duration>,
+    backoff_strategy: Option<BackoffStrategy>,
+    total: Option<Duration>,
+) -> crate::Result<Option<Retry>> {
+    let Some(retries) = retries else {
+        for (set, name) in [
+            (backoff.is_some(), "backoff"),
+            (backoff_strategy.is_some(), "backoff_strategy"),
+            (total.is_some(), "total"),
+        ] {
+            if set {
+                return Err(Error::missing_span(format!(
+                    "'{name}' can only be used together with 'retries'"
+                )));
+            }
+        }
+        return Ok(None);
+    };
+    let backoff = match (backoff, backoff_strategy) {
+        (Some(duration), strategy) => Some(Backoff {
+            duration,
+            strategy: strategy.unwrap_or(BackoffStrategy::Fixed),
+        }),
+        (None, Some(_)) => {
+            return Err(Error::missing_span(
+                "'backoff_strategy' can only be used together with 'backoff'".to_string(),
+            ));
+        }
+        (None, None) => None,
+    };
+    Ok(Some(Retry {
+        retries,
+        backoff,
+        total,
+    }))
+}
+
 pub(crate) struct ValidOpts {
     pub(crate) duration: ParsedDuration,
     pub(crate) on_error: OnError,
+    pub(crate) log: Option<TokenStream>,
+    pub(crate) message: Option<MessageTemplate>,
+    pub(crate) jitter: Option<Jitter>,
+    pub(crate) clamp: Option<Clamp>,
+    pub(crate) retry: Option<Retry>,
+    pub(crate) slow: Option<SlowWarning>,
+    pub(crate) runtime: Runtime,
+}
+
+impl ValidOpts {
+    /// The timeout message, from the `message` template if there is one
+    #[must_use]
+    pub(crate) fn message(&self, fn_name: &str) -> Message {
+        let jitter = self.jitter.as_ref();
+        match &self.message {
+            Some(template) => template.render(fn_name, &self.duration.describe(jitter)),
+            None => Message::text(self.duration.to_error_display(fn_name, jitter)),
+        }
+    }
+
+    /// Whether `log` and `on_error` handlers get a message formatted when the timeout fires,
+    /// with the effective timeout after `jitter`, `min` and `max`, which needs std
+    #[must_use]
+    pub(crate) fn formats_effective_message(&self) -> bool {
+        let takes_message = self.log.is_some() || matches!(self.on_error, OnError::Result(_));
+        (self.jitter.is_some() || self.clamp.is_some())
+            && takes_message
+            && !matches!(self.runtime, Runtime::Embassy)
+    }
+
+    /// The duration of each attempt if it's a literal used as is,
+    /// which runtimes can convert at expansion time
+    #[must_use]
+    pub(crate) fn known_duration(&self) -> Option<Duration> {
+        match self.duration {
+            ParsedDuration::Duration(d)
+                if self.jitter.is_none() && self.clamp.is_none() && self.retry.is_none() =>
+            {
+                Some(d)
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Default)]
 struct Opts {
     duration: Option<ParsedDuration>,
     on_error: Option<OnError>,
+    log: Option<TokenStream>,
+    retries: Option<u32>,
+    backoff: Option<Duration>,
+    backoff_strategy: Option<BackoffStrategy>,
+    total: Option<Duration>,
+    warn_after: Option<Duration>,
+    on_slow: Option<TokenStream>,
+    duration_env: Option<(String, Span)>,
+    strict: Option<Span>,
+    message: Option<MessageTemplate>,
+    jitter: Option<Jitter>,
+    min: Option<Duration>,
+    max: Option<Duration>,
+    on_clamp: Option<TokenStream>,
+    runtime: Option<Runtime>,
+    /// The path of the `crate` attribute, where tokio is found
+    tokio_crate: Option<TokenStream>,
+    /// Every duration literal, checked again once `strict` is known
+    duration_literals: Vec<Literal>,
 }
 
 pub(crate) enum ParsedDuration {
     Duration(Duration),
+    /// `"none"` or `"infinite"`, never timing out
+    Unbounded,
+    /// An expression evaluating to a `Duration`, or an `Option<Duration>` where `None` is unbounded
     Ref(TokenStream),
+    /// Read from an environment variable once, falling back to `default` if unset or invalid
+    Env {
+        var: String,
+        default: Option<Duration>,
+    },
 }
 
 impl ParsedDuration {
+    /// Whether the duration may turn out to be unbounded, evaluating to an `Option<Duration>`
+    #[must_use]
+    pub(crate) fn is_optional(&self) -> bool {
+        !matches!(self, ParsedDuration::Duration(_))
+    }
+
     #[must_use]
-    pub(crate) fn to_error_display(&self, fn_name: &str) -> String {
+    pub(crate) fn to_error_display(&self, fn_name: &str, jitter: Option<&Jitter>) -> String {
         match self {
-            ParsedDuration::Duration(d) => {
+            ParsedDuration::Duration(_) | ParsedDuration::Unbounded => {
+                format!("'{fn_name}' timed out after {}", self.describe(jitter))
+            }
+            ParsedDuration::Ref(_) | ParsedDuration::Env { .. } => {
                 format!(
-                    "'{fn_name}' timed out after {}s{}ns",
-                    d.as_secs(),
-                    d.subsec_nanos()
+                    "'{fn_name}' timed out after duration from {}",
+                    self.describe(jitter)
                 )
             }
-            ParsedDuration::Ref(r) => {
-                format!("'{fn_name}' timed out after duration from {r}")
-            }
         }
     }
 
+    /// What a message's `{duration}` is replaced with
+    #[must_use]
+    pub(crate) fn describe(&self, jitter: Option<&Jitter>) -> String {
+        let mut described = match self {
+            ParsedDuration::Duration(d) => HumanDuration(*d).to_string(),
+            ParsedDuration::Unbounded => "none".to_string(),
+            ParsedDuration::Ref(r) => r.to_string(),
+            ParsedDuration::Env {
+                var,
+                default: Some(default),
+            } => format!("env '{var}' (default {})", HumanDuration(*default)),
+            ParsedDuration::Env { var, default: None } => format!("env '{var}' (default none)"),
+        };
+        if let Some(jitter) = jitter {
+            described.push_str(&jitter.describe());
+        }
+        described
+    }
+
     #[must_use]
     pub(crate) fn into_token_stream(self) -> TokenStream {
         match self {
-            ParsedDuration::Duration(d) => {
-                let secs = d.as_secs();
-                let nanos = d.subsec_nanos();
-                let mut inner_group = TokenStream::new();
-                inner_group.extend([
-                    TokenTree::Literal(Literal::u64_unsuffixed(secs)),
-                    TokenTree::Punct(Punct::new(',', Spacing::Alone)),
-                    TokenTree::Literal(Literal::u32_suffixed(nanos)),
-                ]);
-                let mut ts = TokenStream::new();
-                let span = Span::call_site();
-                ts.extend([
-                    TokenTree::Ident(Ident::new("core", span)),
-                    TokenTree::Punct(Punct::new(':', Spacing::Joint)),
-                    TokenTree::Punct(Punct::new(':', Spacing::Alone)),
-                    TokenTree::Ident(Ident::new("time", span)),
-                    TokenTree::Punct(Punct::new(':', Spacing::Joint)),
-                    TokenTree::Punct(Punct::new(':', Spacing::Alone)),
-                    TokenTree::Ident(Ident::new("Duration", span)),
-                    TokenTree::Punct(Punct::new(':', Spacing::Joint)),
-                    TokenTree::Punct(Punct::new(':', Spacing::Alone)),
-                    TokenTree::Ident(Ident::new("new", span)),
-                    TokenTree::Group(Group::new(Delimiter::Parenthesis, inner_group)),
-                ]);
-                ts
-            }
-            ParsedDuration::Ref(r) => r,
+            ParsedDuration::Duration(d) => crate::tokens::duration(d),
+            ParsedDuration::Unbounded => optional_duration(None),
+            ParsedDuration::Ref(r) => optional_from(r),
+            ParsedDuration::Env { var, default } => env_duration(&var, default),
         }
     }
 }
 
 pub(crate) enum OnError {
     Panic,
+    Default,
+    Into,
     Result(TokenStream),
+    Context(TokenStream),
+    Fallback(TokenStream),
 }
 
 impl OnError {
     #[must_use]
-    pub(crate) fn into_token_stream(self, err_disp: &str) -> TokenStream {
+    pub(crate) fn into_token_stream(
+        self,
+        fn_name: &str,
+        message: &TokenStream,
+        duration: &Ident,
+        error: &Ident,
+        elapsed: &TokenStream,
+        adjusted: bool,
+    ) -> TokenStream {
         match self {
             OnError::Panic => {
                 let mut group = TokenStream::new();
-                group.extend([TokenTree::Literal(Literal::string(err_disp))]);
-                let mut ts = TokenStream::new();
-                let span = Span::call_site();
+                // The static message can only name the configured duration, a jittered or clamped
+                // effective timeout is added here
+                let format = if adjusted {
+                    "{}, effective timeout {:?}"
+                } else {
+                    "{}"
+                };
+                group.extend([
+                    TokenTree::Literal(Literal::string(format)),
+                    crate::tokens::punct(','),
+                ]);
+                group.extend(message.clone());
+                if adjusted {
+                    group.extend([
+                        crate::tokens::punct(','),
+                        TokenTree::Ident(duration.clone()),
+                    ]);
+                }
+                // `::core::panic!`, a `panic` macro in the user's scope can't replace it
+                let mut ts = crate::tokens::abs_path(&["core", "panic"]);
                 ts.extend([
-                    TokenTree::Ident(Ident::new("panic", span)),
                     TokenTree::Punct(Punct::new('!', Spacing::Alone)),
                     TokenTree::Group(Group::new(Delimiter::Parenthesis, group)),
                 ]);
                 ts
             }
+            OnError::Default => {
+                let mut ts = crate::tokens::abs_path(&["core", "default", "Default", "default"]);
+                ts.extend([TokenTree::Group(Group::new(
+                    Delimiter::Parenthesis,
+                    TokenStream::new(),
+                ))]);
+                ts
+            }
+            OnError::Into => into_error(fn_name, duration),
             OnError::Result(e) => {
                 let mut inner_group = TokenStream::new();
-                inner_group.extend([TokenTree::Literal(Literal::string(err_disp))]);
-                let mut outer_group = TokenStream::new();
-                outer_group.extend(e);
+                inner_group.extend(message.clone());
+                let mut outer_group = crate::tokens::callee(e);
                 outer_group.extend([TokenTree::Group(Group::new(
                     Delimiter::Parenthesis,
                     inner_group,
                 ))]);
                 outer_group
             }
+            OnError::Context(e) => context_handler_call(e, fn_name, duration, error, elapsed),
+            // Evaluated lazily, only when the timeout fires
+            OnError::Fallback(e) => TokenStream::from(crate::tokens::braces(e)),
         }
     }
 }
 
+/// `<duration>` as an `Option<Duration>`, through core's `From` impls so that both a `Duration`
+/// and an `Option<Duration>` are accepted without naming `tokio_timeout`, which may be renamed
+///
+/// ```text
+/// <::core::option::Option<::core::time::Duration> as ::core::convert::From<_>>::from(<duration>)
+/// ```
+fn optional_from(duration: TokenStream) -> TokenStream {
+    let mut ts = TokenStream::from_iter([crate::tokens::punct('<')]);
+    ts.extend(crate::tokens::abs_path(&["core", "option", "Option"]));
+    ts.extend([crate::tokens::punct('<')]);
+    ts.extend(crate::tokens::abs_path(&["core", "time", "Duration"]));
+    ts.extend([crate::tokens::punct('>'), crate::tokens::ident("as")]);
+    ts.extend(crate::tokens::abs_path(&["core", "convert", "From"]));
+    ts.extend([
+        crate::tokens::punct('<'),
+        crate::tokens::ident("_"),
+        crate::tokens::punct('>'),
+        crate::tokens::punct('>'),
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        crate::tokens::ident("from"),
+        crate::tokens::parens(duration),
+    ]);
+    ts
+}
+
+/// `::core::option::Option::Some(<duration>)`, or `::core::option::Option::None::<::core::time::Duration>`
+fn optional_duration(duration: Option<Duration>) -> TokenStream {
+    if let Some(d) = duration {
+        let mut ts = crate::tokens::abs_path(&["core", "option", "Option", "Some"]);
+        ts.extend([crate::tokens::parens(crate::tokens::duration(d))]);
+        return ts;
+    }
+    let mut ts = crate::tokens::abs_path(&["core", "option", "Option", "None"]);
+    ts.extend([
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        crate::tokens::punct('<'),
+    ]);
+    ts.extend(crate::tokens::abs_path(&["core", "time", "Duration"]));
+    ts.extend([crate::tokens::punct('>')]);
+    ts
+}
+
+/// Reads the duration from the environment once, caching it for the rest of the process.
+///
+/// ```text
+/// {
+///     static DURATION: ::std::sync::OnceLock<::core::option::Option<::core::time::Duration>> =
+///         ::std::sync::OnceLock::new();
+///     *DURATION.get_or_init(|| ::tokio_timeout::__private::duration_from_env("<var>", <default>))
+/// }
+/// ```
+fn env_duration(var: &str, default: Option<Duration>) -> TokenStream {
+    let cache = crate::tokens::internal_ident("DURATION");
+    let mut ts = TokenStream::from_iter([
+        crate::tokens::ident("static"),
+        TokenTree::Ident(cache.clone()),
+        crate::tokens::punct(':'),
+    ]);
+    ts.extend(crate::tokens::abs_path(&["std", "sync", "OnceLock"]));
+    ts.extend([crate::tokens::punct('<')]);
+    ts.extend(crate::tokens::abs_path(&["core", "option", "Option"]));
+    ts.extend([crate::tokens::punct('<')]);
+    ts.extend(crate::tokens::abs_path(&["core", "time", "Duration"]));
+    ts.extend([
+        crate::tokens::punct('>'),
+        crate::tokens::punct('>'),
+        crate::tokens::punct('='),
+    ]);
+    ts.extend(crate::tokens::abs_path(&["std", "sync", "OnceLock", "new"]));
+    ts.extend([
+        crate::tokens::parens(TokenStream::new()),
+        crate::tokens::punct(';'),
+    ]);
+    let mut read_args = TokenStream::from_iter([
+        TokenTree::Literal(Literal::string(var)),
+        crate::tokens::punct(','),
+    ]);
+    read_args.extend(optional_duration(default));
+    let mut init = TokenStream::from_iter([
+        TokenTree::Punct(Punct::new('|', Spacing::Joint)),
+        TokenTree::Punct(Punct::new('|', Spacing::Alone)),
+    ]);
+    init.extend(crate::tokens::abs_path(&[
+        "tokio_timeout",
+        "__private",
+        "duration_from_env",
+    ]));
+    init.extend([crate::tokens::parens(read_args)]);
+    ts.extend([
+        crate::tokens::punct('*'),
+        TokenTree::Ident(cache),
+        crate::tokens::punct('.'),
+        crate::tokens::ident("get_or_init"),
+        crate::tokens::parens(init),
+    ]);
+    TokenStream::from(crate::tokens::braces(ts))
+}
+
+/// `::core::result::Result::Err(::core::convert::Into::into(
+///     ::tokio_timeout::TimeoutError::new("<fn_name>", duration)))`
+fn into_error(fn_name: &str, duration: &Ident) -> TokenStream {
+    let mut new_args = TokenStream::new();
+    new_args.extend([
+        TokenTree::Literal(Literal::string(fn_name)),
+        TokenTree::Punct(Punct::new(',', Spacing::Alone)),
+        TokenTree::Ident(duration.clone()),
+    ]);
+    let mut into_args = crate::tokens::abs_path(&["tokio_timeout", "TimeoutError", "new"]);
+    into_args.extend([TokenTree::Group(Group::new(
+        Delimiter::Parenthesis,
+        new_args,
+    ))]);
+    let mut err_args = crate::tokens::abs_path(&["core", "convert", "Into", "into"]);
+    err_args.extend([TokenTree::Group(Group::new(
+        Delimiter::Parenthesis,
+        into_args,
+    ))]);
+    let mut ts = crate::tokens::abs_path(&["core", "result", "Result", "Err"]);
+    ts.extend([TokenTree::Group(Group::new(
+        Delimiter::Parenthesis,
+        err_args,
+    ))]);
+    ts
+}
+
+/// `handler(::tokio_timeout::TimeoutContext { fn_name: "<fn_name>", .., error: e })`
+fn context_handler_call(
+    handler: TokenStream,
+    fn_name: &str,
+    duration: &Ident,
+    error: &Ident,
+    elapsed: &TokenStream,
+) -> TokenStream {
+    let mut fields = TokenStream::new();
+    let mut field = |name: &str, value: TokenStream| {
+        fields.extend([crate::tokens::ident(name), crate::tokens::punct(':')]);
+        fields.extend(value);
+        fields.extend([crate::tokens::punct(',')]);
+    };
+    let location_macro = |name: &str| {
+        let mut ts = crate::tokens::abs_path(&["core", name]);
+        ts.extend([
+            crate::tokens::punct('!'),
+            crate::tokens::parens(TokenStream::new()),
+        ]);
+        ts
+    };
+    field(
+        "fn_name",
+        TokenStream::from(TokenTree::Literal(Literal::string(fn_name))),
+    );
+    field("module_path", location_macro("module_path"));
+    field("file", location_macro("file"));
+    field("line", location_macro("line"));
+    field(
+        "duration",
+        TokenStream::from(TokenTree::Ident(duration.clone())),
+    );
+    field("elapsed", elapsed.clone());
+    field("error", TokenStream::from(TokenTree::Ident(error.clone())));
+    let mut ctx = crate::tokens::abs_path(&["tokio_timeout", "TimeoutContext"]);
+    ctx.extend([crate::tokens::braces(fields)]);
+    let mut ts = crate::tokens::callee(handler);
+    ts.extend([crate::tokens::parens(ctx)]);
+    ts
+}
+
 enum Attributes {
     Duration,
     OnError,
+    OnErrorCtx,
+    Fallback,
+    Log,
+    Retries,
+    Backoff,
+    BackoffStrategy,
+    Total,
+    WarnAfter,
+    OnSlow,
+    DurationEnv,
+    Strict,
+    Message,
+    Jitter,
+    Min,
+    Max,
+    OnClamp,
+    Runtime,
+    Backend,
+    Crate,
 }
 
-fn take_next(cur: &mut Opts, it: &mut impl Iterator<Item = TokenTree>) -> crate::Result<bool> {
-    let (attrs, id) = loop {
+fn next_attribute(
+    it: &mut (impl Iterator<Item = TokenTree> + Clone),
+) -> crate::Result<Option<(Attributes, Ident)>> {
+    loop {
         let Some(next) = it.next() else {
-            return Ok(false);
+            return Ok(None);
         };
         match next {
             TokenTree::Ident(id) => {
-                break match id.to_string().as_str() {
+                return Ok(Some(match id.to_string().as_str() {
                     "duration" => (Attributes::Duration, id),
                     "on_error" => (Attributes::OnError, id),
+                    "on_error_ctx" => (Attributes::OnErrorCtx, id),
+                    "fallback" => (Attributes::Fallback, id),
+                    "log" => (Attributes::Log, id),
+                    "retries" => (Attributes::Retries, id),
+                    "backoff" => (Attributes::Backoff, id),
+                    "backoff_strategy" => (Attributes::BackoffStrategy, id),
+                    "total" => (Attributes::Total, id),
+                    "warn_after" => (Attributes::WarnAfter, id),
+                    "on_slow" => (Attributes::OnSlow, id),
+                    "duration_env" => (Attributes::DurationEnv, id),
+                    "strict" => (Attributes::Strict, id),
+                    "message" => (Attributes::Message, id),
+                    "jitter" => (Attributes::Jitter, id),
+                    "min" => (Attributes::Min, id),
+                    "max" => (Attributes::Max, id),
+                    "on_clamp" => (Attributes::OnClamp, id),
+                    "runtime" => (Attributes::Runtime, id),
+                    "backend" => (Attributes::Backend, id),
+                    "crate" => (Attributes::Crate, id),
                     unk => {
                         return Err(Error::with_span(
                             id.span(),
                             format!("Unknown attribute: {unk}"),
                         ));
                     }
-                };
+                }));
             }
             // Allow a punct from the last round
             TokenTree::Punct(p) => {
@@ -161,7 +665,82 @@ fn take_next(cur: &mut Opts, it: &mut impl Iterator<Item = TokenTree>) -> crate:
                 ));
             }
         }
+    }
+}
+
+fn take_next(
+    cur: &mut Opts,
+    it: &mut (impl Iterator<Item = TokenTree> + Clone),
+) -> crate::Result<bool> {
+    let Some((attrs, id)) = next_attribute(it)? else {
+        return Ok(false);
     };
+    match attrs {
+        Attributes::Duration
+        | Attributes::DurationEnv
+        | Attributes::Strict
+        | Attributes::Jitter => {
+            take_duration(cur, &attrs, &id, it)?;
+        }
+        Attributes::Retries
+        | Attributes::Backoff
+        | Attributes::BackoffStrategy
+        | Attributes::Total => {
+            take_retry(cur, &attrs, &id, it)?;
+        }
+        Attributes::Min | Attributes::Max | Attributes::OnClamp => {
+            take_clamp(cur, &attrs, &id, it)?;
+        }
+        Attributes::OnError | Attributes::OnErrorCtx | Attributes::Fallback => {
+            take_on_error(cur, &attrs, &id, it)?;
+        }
+        Attributes::Log => {
+            if cur.log.is_some() {
+                return Err(Error::with_span(id.span(), "Duplicate 'log' attribute"));
+            }
+            take_next_equals(it, "log").map_err(|e| e.with_span_if_missing(id.span()))?;
+            cur.log = Some(parse_log(it).map_err(|e| e.with_span_if_missing(id.span()))?);
+        }
+        Attributes::WarnAfter => {
+            take_attr_start(cur.warn_after.is_some(), &id, "warn_after", it)?;
+            cur.warn_after = Some(parse_duration_literal(
+                it,
+                "warn_after",
+                &id,
+                &mut cur.duration_literals,
+            )?);
+        }
+        Attributes::OnSlow => {
+            take_attr_start(cur.on_slow.is_some(), &id, "on_slow", it)?;
+            let handler = take_expr(it);
+            if handler.is_empty() {
+                return Err(Error::with_span(
+                    id.span(),
+                    "Expected 'on_slow' token, got nothing",
+                ));
+            }
+            cur.on_slow = Some(handler);
+        }
+        Attributes::Message => {
+            take_attr_start(cur.message.is_some(), &id, "message", it)?;
+            let lit = take_literal(it, "message").map_err(|e| e.with_span_if_missing(id.span()))?;
+            cur.message = Some(parse_message(&lit)?);
+        }
+        Attributes::Runtime | Attributes::Backend | Attributes::Crate => {
+            take_runtime(cur, &attrs, &id, it)?;
+        }
+    }
+
+    Ok(true)
+}
+
+/// `duration`, and the attributes changing how it's read, `duration_env`, `strict` and `jitter`
+fn take_duration(
+    cur: &mut Opts,
+    attrs: &Attributes,
+    id: &Ident,
+    it: &mut (impl Iterator<Item = TokenTree> + Clone),
+) -> crate::Result<()> {
     match attrs {
         Attributes::Duration => {
             if cur.duration.is_some() {
@@ -171,25 +750,305 @@ fn take_next(cur: &mut Opts, it: &mut impl Iterator<Item = TokenTree>) -> crate:
                 ));
             }
             take_next_equals(it, "duration").map_err(|e| e.with_span_if_missing(id.span()))?;
-            cur.duration = Some(parse_duration(it)?);
+            cur.duration = Some(
+                parse_duration(it, &mut cur.duration_literals)
+                    .map_err(|e| e.with_span_if_missing(id.span()))?,
+            );
+        }
+        Attributes::DurationEnv => {
+            take_attr_start(cur.duration_env.is_some(), id, "duration_env", it)?;
+            let lit =
+                take_literal(it, "duration_env").map_err(|e| e.with_span_if_missing(id.span()))?;
+            let var = lit.to_string().trim_matches('"').to_string();
+            if var.is_empty() {
+                return Err(Error::with_span(
+                    lit.span(),
+                    "Expected 'duration_env' to be a non-empty str literal",
+                ));
+            }
+            cur.duration_env = Some((var, lit.span()));
+        }
+        Attributes::Strict => {
+            // A flag, without a value
+            if cur.strict.is_some() {
+                return Err(Error::with_span(id.span(), "Duplicate 'strict' attribute"));
+            }
+            cur.strict = Some(id.span());
+        }
+        Attributes::Jitter => {
+            take_attr_start(cur.jitter.is_some(), id, "jitter", it)?;
+            let lit = take_literal(it, "jitter").map_err(|e| e.with_span_if_missing(id.span()))?;
+            let value = lit.to_string();
+            let value = value.trim_matches('"');
+            cur.jitter = Some(if let Some(percent) = crate::jitter::parse_percent(value) {
+                Jitter::Percent(percent.map_err(|e| Error::with_span(lit.span(), e))?)
+            } else {
+                let jitter = timeout_duration::parse_duration(value)
+                    .map_err(|e| Error::with_span(lit.span(), e))?;
+                cur.duration_literals.push(lit);
+                Jitter::Fixed(jitter)
+            });
         }
-        Attributes::OnError => {
-            if cur.on_error.is_some() {
+        _ => unreachable!("only called with duration attributes"),
+    }
+    Ok(())
+}
+
+/// `min` and `max`, bounding a duration evaluated at runtime, and `on_clamp`
+fn take_clamp(
+    cur: &mut Opts,
+    attrs: &Attributes,
+    id: &Ident,
+    it: &mut (impl Iterator<Item = TokenTree> + Clone),
+) -> crate::Result<()> {
+    match attrs {
+        Attributes::Min => {
+            take_attr_start(cur.min.is_some(), id, "min", it)?;
+            cur.min = Some(parse_duration_literal(
+                it,
+                "min",
+                id,
+                &mut cur.duration_literals,
+            )?);
+        }
+        Attributes::Max => {
+            take_attr_start(cur.max.is_some(), id, "max", it)?;
+            cur.max = Some(parse_duration_literal(
+                it,
+                "max",
+                id,
+                &mut cur.duration_literals,
+            )?);
+        }
+        Attributes::OnClamp => {
+            take_attr_start(cur.on_clamp.is_some(), id, "on_clamp", it)?;
+            let handler = take_expr(it);
+            if handler.is_empty() {
                 return Err(Error::with_span(
                     id.span(),
-                    "Duplicate 'on_error' attribute",
+                    "Expected 'on_clamp' token, got nothing",
                 ));
             }
-            take_next_equals(it, "on_error").map_err(|e| e.with_span_if_missing(id.span()))?;
-            cur.on_error = Some(parse_on_error(it).map_err(|e| e.with_span_if_missing(id.span()))?);
+            cur.on_clamp = Some(handler);
         }
+        _ => unreachable!("only called with clamp attributes"),
     }
+    Ok(())
+}
 
-    Ok(true)
+/// `retries`, and the attributes tuning them, `backoff`, `backoff_strategy` and `total`
+fn take_retry(
+    cur: &mut Opts,
+    attrs: &Attributes,
+    id: &Ident,
+    it: &mut (impl Iterator<Item = TokenTree> + Clone),
+) -> crate::Result<()> {
+    match attrs {
+        Attributes::Retries => {
+            take_attr_start(cur.retries.is_some(), id, "retries", it)?;
+            let lit = take_literal(it, "retries").map_err(|e| e.with_span_if_missing(id.span()))?;
+            cur.retries = Some(parse_retries(&lit)?);
+        }
+        Attributes::Backoff => {
+            take_attr_start(cur.backoff.is_some(), id, "backoff", it)?;
+            cur.backoff = Some(parse_duration_literal(
+                it,
+                "backoff",
+                id,
+                &mut cur.duration_literals,
+            )?);
+        }
+        Attributes::BackoffStrategy => {
+            take_attr_start(cur.backoff_strategy.is_some(), id, "backoff_strategy", it)?;
+            let lit = take_literal(it, "backoff_strategy")
+                .map_err(|e| e.with_span_if_missing(id.span()))?;
+            cur.backoff_strategy = Some(parse_backoff_strategy(&lit)?);
+        }
+        Attributes::Total => {
+            take_attr_start(cur.total.is_some(), id, "total", it)?;
+            cur.total = Some(parse_duration_literal(
+                it,
+                "total",
+                id,
+                &mut cur.duration_literals,
+            )?);
+        }
+        _ => unreachable!("only called with retry attributes"),
+    }
+    Ok(())
+}
+
+/// `runtime`, or a `backend` timer replacing it, and the `crate` path to tokio
+fn take_runtime(
+    cur: &mut Opts,
+    attrs: &Attributes,
+    id: &Ident,
+    it: &mut (impl Iterator<Item = TokenTree> + Clone),
+) -> crate::Result<()> {
+    if let Attributes::Crate = attrs {
+        take_attr_start(cur.tokio_crate.is_some(), id, "crate", it)?;
+        let path = take_expr(it);
+        if path.is_empty() {
+            return Err(Error::with_span(
+                id.span(),
+                "Expected 'crate' token, got nothing",
+            ));
+        }
+        cur.tokio_crate = Some(path);
+        return Ok(());
+    }
+    let name = match attrs {
+        Attributes::Runtime => "runtime",
+        _ => "backend",
+    };
+    if cur.runtime.is_some() {
+        return Err(Error::with_span(
+            id.span(),
+            format!("Duplicate '{name}' attribute, 'runtime' and 'backend' are mutually exclusive"),
+        ));
+    }
+    take_next_equals(it, name).map_err(|e| e.with_span_if_missing(id.span()))?;
+    cur.runtime = Some(if let Attributes::Runtime = attrs {
+        let lit = take_literal(it, "runtime").map_err(|e| e.with_span_if_missing(id.span()))?;
+        Runtime::parse(lit.to_string().trim_matches('"'))
+            .map_err(|e| Error::with_span(lit.span(), e))?
+    } else {
+        let path = take_expr(it);
+        if path.is_empty() {
+            return Err(Error::with_span(
+                id.span(),
+                "Expected 'backend' token, got nothing",
+            ));
+        }
+        Runtime::Backend(path)
+    });
+    Ok(())
+}
+
+/// `on_error`, `on_error_ctx` and `fallback` all decide what happens on timeout
+fn take_on_error(
+    cur: &mut Opts,
+    attrs: &Attributes,
+    id: &Ident,
+    it: &mut (impl Iterator<Item = TokenTree> + Clone),
+) -> crate::Result<()> {
+    let name = match attrs {
+        Attributes::OnError => "on_error",
+        Attributes::OnErrorCtx => "on_error_ctx",
+        _ => "fallback",
+    };
+    if cur.on_error.is_some() {
+        return Err(Error::with_span(
+            id.span(),
+            format!("Duplicate '{name}' attribute, 'on_error', 'on_error_ctx' and 'fallback' are mutually exclusive"),
+        ));
+    }
+    take_next_equals(it, name).map_err(|e| e.with_span_if_missing(id.span()))?;
+    let on_error = if let Attributes::OnError = attrs {
+        parse_on_error(it).map_err(|e| e.with_span_if_missing(id.span()))?
+    } else {
+        let expr = take_expr(it);
+        if expr.is_empty() {
+            return Err(Error::with_span(
+                id.span(),
+                format!("Expected '{name}' token, got nothing"),
+            ));
+        }
+        if let Attributes::OnErrorCtx = attrs {
+            OnError::Context(expr)
+        } else {
+            OnError::Fallback(expr)
+        }
+    };
+    cur.on_error = Some(on_error);
+    Ok(())
+}
+
+fn parse_message(lit: &Literal) -> crate::Result<MessageTemplate> {
+    let template = crate::message::string_value(lit).ok_or_else(|| {
+        Error::with_span(
+            lit.span(),
+            "Expected 'message' to be a str literal, with no escapes other than \\n, \\t, \\r, \\0, \\\\, \\\" and \\'",
+        )
+    })?;
+    MessageTemplate::parse(&template).map_err(|e| Error::with_span(lit.span(), e))
+}
+
+fn parse_retries(lit: &Literal) -> crate::Result<u32> {
+    let retries = lit.to_string().parse::<u32>().map_err(|e| {
+        Error::with_span(
+            lit.span(),
+            format!("Expected 'retries' to be an integer literal, got {lit}: {e}"),
+        )
+    })?;
+    if retries == 0 {
+        return Err(Error::with_span(
+            lit.span(),
+            "Expected 'retries' to be at least 1",
+        ));
+    }
+    Ok(retries)
+}
+
+fn parse_backoff_strategy(lit: &Literal) -> crate::Result<BackoffStrategy> {
+    match lit.to_string().trim_matches('"') {
+        "fixed" => Ok(BackoffStrategy::Fixed),
+        "exponential" => Ok(BackoffStrategy::Exponential),
+        _ => Err(Error::with_span(
+            lit.span(),
+            format!("Expected 'backoff_strategy' to be 'fixed' or 'exponential', got {lit}"),
+        )),
+    }
+}
+
+/// Checks that the attribute isn't a duplicate, and takes the `=` following it
+fn take_attr_start(
+    already_set: bool,
+    id: &Ident,
+    attr: &'static str,
+    it: &mut (impl Iterator<Item = TokenTree> + Clone),
+) -> crate::Result<()> {
+    if already_set {
+        return Err(Error::with_span(
+            id.span(),
+            format!("Duplicate '{attr}' attribute"),
+        ));
+    }
+    take_next_equals(it, attr).map_err(|e| e.with_span_if_missing(id.span()))
+}
+
+fn take_literal(
+    it: &mut (impl Iterator<Item = TokenTree> + Clone),
+    attr: &'static str,
+) -> crate::Result<Literal> {
+    match it.next() {
+        Some(TokenTree::Literal(lit)) => Ok(lit),
+        Some(t) => Err(Error::with_span(
+            t.span(),
+            format!("Expected '{attr}' literal, got '{t}'"),
+        )),
+        None => Err(Error::missing_span(format!(
+            "Expected '{attr}' literal, got nothing"
+        ))),
+    }
+}
+
+/// A duration that has to be specified as a string literal, such as `"100ms"`
+fn parse_duration_literal(
+    it: &mut (impl Iterator<Item = TokenTree> + Clone),
+    attr: &'static str,
+    id: &Ident,
+    literals: &mut Vec<Literal>,
+) -> crate::Result<Duration> {
+    let lit = take_literal(it, attr).map_err(|e| e.with_span_if_missing(id.span()))?;
+    let duration = timeout_duration::parse_duration(lit.to_string().trim_matches('"'))
+        .map_err(|e| Error::with_span(lit.span(), e))?;
+    literals.push(lit);
+    Ok(duration)
 }
 
 fn take_next_equals(
-    it: &mut impl Iterator<Item = TokenTree>,
+    it: &mut (impl Iterator<Item = TokenTree> + Clone),
     attr: &'static str,
 ) -> crate::Result<()> {
     let Some(next) = it.next() else {
@@ -212,86 +1071,98 @@ fn take_next_equals(
     Ok(())
 }
 
-fn parse_duration(it: &mut impl Iterator<Item = TokenTree>) -> crate::Result<ParsedDuration> {
-    let Some(mut next) = it.next() else {
+fn parse_duration(
+    it: &mut (impl Iterator<Item = TokenTree> + Clone),
+    literals: &mut Vec<Literal>,
+) -> crate::Result<ParsedDuration> {
+    if let Some(TokenTree::Literal(lit)) = it.clone().next() {
+        it.next();
+        let lit_s = lit.to_string();
+        let Some(duration) = timeout_duration::parse_timeout(lit_s.trim_matches('"'))
+            .map_err(|e| Error::with_span(lit.span(), e))?
+        else {
+            return Ok(ParsedDuration::Unbounded);
+        };
+        literals.push(lit);
+        return Ok(ParsedDuration::Duration(duration));
+    }
+    // Any other expression is evaluated at the start of each call
+    let stream = take_expr(it);
+    if stream.is_empty() {
         return Err(Error::missing_span(
             "Expected duration token, got nothing".to_string(),
         ));
-    };
-    let mut stream = TokenStream::new();
-    loop {
-        match &next {
-            TokenTree::Ident(_id) => {
-                stream.extend([next]);
-            }
-            TokenTree::Literal(lit) => {
-                return Ok(ParsedDuration::Duration(
-                    crate::parse_duration::parse_duration(lit.to_string().as_str())
-                        .map_err(|e| Error::with_span(lit.span(), e))?,
-                ))
-            }
-            TokenTree::Punct(p) => {
-                if p.as_char() == ',' {
-                    break Ok(ParsedDuration::Ref(stream));
-                }
-                stream.extend([next]);
-            }
-            t @ TokenTree::Group(_) => {
-                return Err(Error::with_span(
-                    t.span(),
-                    format!("Expected duration literal or ident, got '{t}'"),
-                ))
-            }
-        }
-        if let Some(n) = it.next() {
-            next = n;
-        } else {
-            break Ok(ParsedDuration::Ref(stream));
-        }
     }
+    Ok(ParsedDuration::Ref(stream))
 }
 
-fn parse_on_error(it: &mut impl Iterator<Item = TokenTree>) -> crate::Result<OnError> {
-    let Some(mut next) = it.next() else {
-        return Err(Error::ParseSpanMissing(
+fn parse_on_error(it: &mut (impl Iterator<Item = TokenTree> + Clone)) -> crate::Result<OnError> {
+    if let Some(TokenTree::Literal(lit)) = it.clone().next() {
+        it.next();
+        let lit_s = lit.to_string();
+        let lit_s = lit_s.trim_matches('"');
+        return match lit_s {
+            "panic" => Ok(OnError::Panic),
+            "default" => Ok(OnError::Default),
+            "into" => Ok(OnError::Into),
+            _ => Err(Error::with_span(
+                lit.span(),
+                format!(
+                    "Got 'on_error' str literal, expected 'panic', 'default' or 'into', got {lit}"
+                ),
+            )),
+        };
+    }
+    let stream = take_expr(it);
+    if stream.is_empty() {
+        return Err(Error::missing_span(
             "Expected 'on_error' token, got nothing".to_string(),
         ));
-    };
+    }
+    Ok(OnError::Result(stream))
+}
+
+fn parse_log(it: &mut (impl Iterator<Item = TokenTree> + Clone)) -> crate::Result<TokenStream> {
+    let stream = take_expr(it);
+    if stream.is_empty() {
+        return Err(Error::missing_span(
+            "Expected 'log' token, got nothing".to_string(),
+        ));
+    }
+    Ok(stream)
+}
+
+/// Takes an arbitrary expression up until the comma starting the next attribute, or the end of
+/// the attribute. Commas inside groups (parenthesis, brackets, braces) are part of the expression,
+/// as are commas in generics `<A, B>` and closure parameters `|a, b|`, which aren't groups.
+fn take_expr(it: &mut (impl Iterator<Item = TokenTree> + Clone)) -> TokenStream {
     let mut stream = TokenStream::new();
-    loop {
-        match &next {
-            TokenTree::Literal(lit) => {
-                let lit_s = lit.to_string();
-                let lit_s = lit_s.trim_matches('"');
-                return if lit_s == "panic" {
-                    Ok(OnError::Panic)
-                } else {
-                    Err(Error::with_span(
-                        lit.span(),
-                        format!("Got 'on_error' str literal, expected only 'panic', got {lit}"),
-                    ))
-                };
+    while let Some(next) = it.next() {
+        if let TokenTree::Punct(p) = &next {
+            if p.as_char() == ',' && starts_attribute(it.clone()) {
+                break;
             }
-            TokenTree::Ident(_id) => {
-                stream.extend([next]);
-            }
-            TokenTree::Punct(p) => {
-                if p.as_char() == ',' {
-                    break Ok(OnError::Result(stream));
-                }
-                stream.extend([next]);
-            }
-            t @ TokenTree::Group(_) => {
-                return Err(Error::with_span(
-                    t.span(),
-                    format!("Expected 'on_error' str literal or ident, got '{t}'"),
-                ));
-            }
-        }
-        if let Some(n) = it.next() {
-            next = n;
-        } else {
-            break Ok(OnError::Result(stream));
         }
+        stream.extend([next]);
+    }
+    stream
+}
+
+/// Whether the tokens after a comma start the next attribute, `<name> =` or the `strict` flag,
+/// or are the end of the attribute
+fn starts_attribute(mut rest: impl Iterator<Item = TokenTree>) -> bool {
+    let TokenTree::Ident(name) = (match rest.next() {
+        Some(next) => next,
+        None => return true,
+    }) else {
+        return false;
+    };
+    let is_flag = matches!(name.to_string().as_str(), "strict");
+    match rest.next() {
+        // `=` alone, not the start of `==` or `=>`
+        Some(TokenTree::Punct(p)) if p.as_char() == '=' => p.spacing() == Spacing::Alone,
+        Some(TokenTree::Punct(p)) if p.as_char() == ',' => is_flag,
+        None => is_flag,
+        _ => false,
     }
 }
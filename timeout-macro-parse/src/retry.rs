@@ -0,0 +1,230 @@
+use crate::message::Message;
+use crate::runtime::Runtime;
+use crate::tokens::{abs_path, braces, ident, internal_ident, let_binding, parens, punct};
+#[cfg(not(feature = "test"))]
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, TokenStream, TokenTree};
+#[cfg(feature = "test")]
+use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, TokenStream, TokenTree};
+use std::time::Duration;
+use timeout_duration::HumanDuration;
+
+pub(crate) struct Retry {
+    pub(crate) retries: u32,
+    pub(crate) backoff: Option<Backoff>,
+    pub(crate) total: Option<Duration>,
+}
+
+pub(crate) struct Backoff {
+    pub(crate) duration: Duration,
+    pub(crate) strategy: BackoffStrategy,
+}
+
+#[derive(Copy, Clone)]
+pub(crate) enum BackoffStrategy {
+    Fixed,
+    Exponential,
+}
+
+impl Retry {
+    fn attempts(&self) -> u32 {
+        self.retries.saturating_add(1)
+    }
+
+    /// An expression evaluating to the `&'static str` describing why the last attempt failed.
+    #[must_use]
+    pub(crate) fn message(&self, err_disp: &Message, attempt: &Ident) -> TokenStream {
+        let attempts = self.attempts();
+        let exhausted = format!(", {attempts} of {attempts} attempts timed out");
+        let Some(total) = self.total else {
+            return err_disp.to_token_stream(&exhausted);
+        };
+        // `["<budget exhausted on attempt 1>", .., "<exhausted>"][(attempt - 1) as usize]`
+        let total = HumanDuration(total);
+        let mut messages = TokenStream::new();
+        for i in 1..attempts {
+            messages.extend(err_disp.to_token_stream(&format!(
+                ", total budget of {total} exhausted after {i} of {attempts} attempts"
+            )));
+            messages.extend([punct(',')]);
+        }
+        messages.extend(err_disp.to_token_stream(&exhausted));
+        let mut index = TokenStream::from_iter([
+            parens(TokenStream::from_iter([
+                TokenTree::Ident(attempt.clone()),
+                punct('-'),
+                TokenTree::Literal(Literal::u32_unsuffixed(1)),
+            ])),
+            ident("as"),
+        ]);
+        index.extend(abs_path(&["core", "primitive", "usize"]));
+        TokenStream::from_iter([
+            TokenTree::Group(Group::new(Delimiter::Bracket, messages)),
+            TokenTree::Group(Group::new(Delimiter::Bracket, index)),
+        ])
+    }
+
+    /// The duration to give a single attempt, `duration` cut short by the remaining total budget
+    #[must_use]
+    pub(crate) fn attempt_duration(&self, duration: &Ident, elapsed: &TokenStream) -> TokenStream {
+        let Some(total) = self.total else {
+            return TokenStream::from(TokenTree::Ident(duration.clone()));
+        };
+        // `::core::cmp::min(duration, <total>.saturating_sub(started.elapsed()))`
+        let mut remaining = crate::tokens::duration(total);
+        remaining.extend([punct('.'), ident("saturating_sub"), parens(elapsed.clone())]);
+        let mut args = TokenStream::from(TokenTree::Ident(duration.clone()));
+        args.extend([punct(',')]);
+        args.extend(remaining);
+        let mut ts = abs_path(&["core", "cmp", "min"]);
+        ts.extend([parens(args)]);
+        ts
+    }
+
+    /// Runs `attempt_call`, a timeout-wrapped attempt, until it succeeds, the attempts run out,
+    /// or the total budget is exhausted, leaving the last attempt's result in `result`.
+    /// Each attempt gets a clone of the arguments taken `by_value`, which the body may consume.
+    ///
+    /// ```text
+    /// let mut attempt = 0u32;
+    /// let result = loop {
+    ///     attempt += 1;
+    ///     let arg = ::core::clone::Clone::clone(&arg);
+    ///     let result = <attempt_call>;
+    ///     let backoff = <backoff>;
+    ///     if result.is_ok() || attempt > <retries> || started.elapsed() + backoff >= <total> {
+    ///         break result;
+    ///     }
+    ///     ::tokio::time::sleep(backoff).await;
+    /// };
+    /// ```
+    #[must_use]
+    pub(crate) fn into_loop(
+        self,
+        attempt: &Ident,
+        result: &Ident,
+        elapsed: &TokenStream,
+        by_value: &[Ident],
+        attempt_call: TokenStream,
+        runtime: &Runtime,
+    ) -> TokenStream {
+        let backoff = internal_ident("backoff");
+        let mut loop_body = TokenStream::from_iter([
+            TokenTree::Ident(attempt.clone()),
+            TokenTree::Punct(Punct::new('+', Spacing::Joint)),
+            TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+            TokenTree::Literal(Literal::u32_unsuffixed(1)),
+            punct(';'),
+        ]);
+        for arg in by_value {
+            loop_body.extend(clone_arg(arg));
+        }
+        loop_body.extend(let_binding(result, false, attempt_call));
+        if let Some(b) = &self.backoff {
+            loop_body.extend(let_binding(&backoff, false, b.to_token_stream(attempt)));
+        }
+        // `::core::result::Result::is_ok(&result) || attempt > <retries>`
+        let mut done = abs_path(&["core", "result", "Result", "is_ok"]);
+        done.extend([
+            parens(TokenStream::from_iter([
+                punct('&'),
+                TokenTree::Ident(result.clone()),
+            ])),
+            TokenTree::Punct(Punct::new('|', Spacing::Joint)),
+            TokenTree::Punct(Punct::new('|', Spacing::Alone)),
+            TokenTree::Ident(attempt.clone()),
+            punct('>'),
+            TokenTree::Literal(Literal::u32_unsuffixed(self.retries)),
+        ]);
+        if let Some(total) = self.total {
+            // `|| started.elapsed()[.saturating_add(backoff)] >= <total>`
+            done.extend([
+                TokenTree::Punct(Punct::new('|', Spacing::Joint)),
+                TokenTree::Punct(Punct::new('|', Spacing::Alone)),
+            ]);
+            done.extend(elapsed.clone());
+            if self.backoff.is_some() {
+                done.extend([
+                    punct('.'),
+                    ident("saturating_add"),
+                    parens(TokenStream::from(TokenTree::Ident(backoff.clone()))),
+                ]);
+            }
+            done.extend([
+                TokenTree::Punct(Punct::new('>', Spacing::Joint)),
+                TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+            ]);
+            done.extend(crate::tokens::duration(total));
+        }
+        loop_body.extend([ident("if")]);
+        loop_body.extend(done);
+        loop_body.extend([braces(TokenStream::from_iter([
+            ident("break"),
+            TokenTree::Ident(result.clone()),
+            punct(';'),
+        ]))]);
+        if self.backoff.is_some() {
+            // `::tokio::time::sleep(backoff).await;`
+            loop_body.extend(runtime.sleep(TokenStream::from(TokenTree::Ident(backoff))));
+            loop_body.extend([punct(';')]);
+        }
+        let mut ts = let_binding(
+            attempt,
+            true,
+            TokenStream::from(TokenTree::Literal(Literal::u32_suffixed(0))),
+        );
+        ts.extend(let_binding(
+            result,
+            false,
+            TokenStream::from_iter([ident("loop"), braces(loop_body)]),
+        ));
+        ts
+    }
+}
+
+/// `let arg = ::core::clone::Clone::clone(&arg);`, spanned to the argument so that
+/// a missing `Clone` impl points at it
+fn clone_arg(arg: &Ident) -> TokenStream {
+    let span = arg.span();
+    let path_sep = || {
+        let mut joint = Punct::new(':', Spacing::Joint);
+        joint.set_span(span);
+        let mut alone = Punct::new(':', Spacing::Alone);
+        alone.set_span(span);
+        [TokenTree::Punct(joint), TokenTree::Punct(alone)]
+    };
+    let mut clone = TokenStream::new();
+    for segment in ["core", "clone", "Clone", "clone"] {
+        clone.extend(path_sep());
+        clone.extend([TokenTree::Ident(Ident::new(segment, span))]);
+    }
+    let mut reference = Punct::new('&', Spacing::Alone);
+    reference.set_span(span);
+    let mut args = Group::new(
+        Delimiter::Parenthesis,
+        TokenStream::from_iter([TokenTree::Punct(reference), TokenTree::Ident(arg.clone())]),
+    );
+    args.set_span(span);
+    clone.extend([TokenTree::Group(args)]);
+    let_binding(arg, false, clone)
+}
+
+impl Backoff {
+    fn to_token_stream(&self, attempt: &Ident) -> TokenStream {
+        let mut ts = crate::tokens::duration(self.duration);
+        if let BackoffStrategy::Exponential = self.strategy {
+            // `<backoff>.saturating_mul(2u32.saturating_pow(attempt - 1))`
+            let pow = TokenStream::from_iter([
+                TokenTree::Literal(Literal::u32_suffixed(2)),
+                punct('.'),
+                ident("saturating_pow"),
+                parens(TokenStream::from_iter([
+                    TokenTree::Ident(attempt.clone()),
+                    punct('-'),
+                    TokenTree::Literal(Literal::u32_unsuffixed(1)),
+                ])),
+            ]);
+            ts.extend([punct('.'), ident("saturating_mul"), parens(pow)]);
+        }
+        ts
+    }
+}
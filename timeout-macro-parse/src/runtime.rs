@@ -0,0 +1,242 @@
+//! The async runtime whose timer the expanded code uses, picked with `runtime = "<name>"`
+//! or `backend = <path>` for an implementation of `tokio_timeout::Timer`, tokio by default.
+//! The other runtimes' features only make them available, they never change the default.
+//! tokio is found at `::tokio`, or at tokio-timeout's re-export with its `tokio` feature,
+//! unless a `crate = <path>` attribute points elsewhere.
+use crate::tokens::{abs_path, ident, parens, punct};
+#[cfg(not(feature = "test"))]
+use proc_macro::{Ident, Literal, Punct, Spacing, TokenStream, TokenTree};
+#[cfg(feature = "test")]
+use proc_macro2::{Ident, Literal, Punct, Spacing, TokenStream, TokenTree};
+use std::time::Duration;
+
+pub(crate) enum Runtime {
+    /// tokio at the given path, the default from [`tokio_path`] if `None`
+    Tokio(Option<TokenStream>),
+    AsyncStd,
+    Smol,
+    Embassy,
+    /// A type implementing `tokio_timeout::Timer`
+    Backend(TokenStream),
+}
+
+impl Runtime {
+    /// Parses the value of the `runtime` attribute
+    pub(crate) fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "tokio" => Ok(Runtime::Tokio(None)),
+            "async-std" if cfg!(feature = "async-std") => Ok(Runtime::AsyncStd),
+            "smol" if cfg!(feature = "smol") => Ok(Runtime::Smol),
+            "embassy" => Ok(Runtime::Embassy),
+            "async-std" => Err(
+                "runtime 'async-std' requires the 'async-std' feature of tokio-timeout".to_string(),
+            ),
+            "smol" => {
+                Err("runtime 'smol' requires the 'smol' feature of tokio-timeout".to_string())
+            }
+            unk => Err(format!(
+                "Unknown runtime '{unk}', expected one of 'tokio', 'async-std', 'smol' or 'embassy'"
+            )),
+        }
+    }
+
+    /// Points tokio at the path of a `crate` attribute, other runtimes don't take a path
+    pub(crate) fn with_crate(self, path: Option<TokenStream>) -> Result<Self, String> {
+        match (self, path) {
+            (Runtime::Tokio(_), Some(path)) => Ok(Runtime::Tokio(Some(path))),
+            (_, Some(_)) => Err("'crate' can only be used with the tokio runtime".to_string()),
+            (runtime, None) => Ok(runtime),
+        }
+    }
+
+    /// `<timeout>(<duration>, <fut>).await`, a `Result` with the runtime's timeout error.
+    /// `known` is the duration if it's known at expansion time.
+    ///
+    /// ```text
+    /// ::tokio::time::timeout(<duration>, <fut>).await
+    /// ::my_rt::tokio::time::timeout(<duration>, <fut>).await
+    /// ::async_std::future::timeout(<duration>, <fut>).await
+    /// ::embassy_time::with_timeout(::embassy_time::Duration::from_micros(<micros>), <fut>).await
+    /// ```
+    ///
+    /// smol has no timeout function, the body is raced against async-io's timer instead,
+    /// by `tokio-timeout`, the body winning ties. A backend is raced the same way.
+    ///
+    /// ```text
+    /// ::tokio_timeout::__private::race(::async_io::Timer::after(<duration>), <fut>).await
+    /// ::tokio_timeout::__private::timeout::<path::to::MyTimer, _>(<duration>, <fut>).await
+    /// ```
+    #[must_use]
+    pub(crate) fn timeout(
+        &self,
+        duration: TokenStream,
+        known: Option<Duration>,
+        fut: TokenStream,
+    ) -> TokenStream {
+        let mut args = match self {
+            Runtime::Smol => {
+                let mut ts = abs_path(&["async_io", "Timer", "after"]);
+                ts.extend([parens(duration)]);
+                ts
+            }
+            Runtime::Embassy => embassy_duration(duration, known),
+            _ => duration,
+        };
+        args.extend([punct(',')]);
+        args.extend(fut);
+        let mut ts = match self {
+            Runtime::Tokio(path) => tokio_path(path.as_ref(), &["time", "timeout"]),
+            Runtime::AsyncStd => abs_path(&["async_std", "future", "timeout"]),
+            Runtime::Smol => abs_path(&["tokio_timeout", "__private", "race"]),
+            Runtime::Embassy => abs_path(&["embassy_time", "with_timeout"]),
+            Runtime::Backend(path) => {
+                let mut ts = abs_path(&["tokio_timeout", "__private", "timeout"]);
+                ts.extend([
+                    TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+                    TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+                    punct('<'),
+                ]);
+                ts.extend(path.clone());
+                ts.extend([punct(','), ident("_"), punct('>')]);
+                ts
+            }
+        };
+        ts.extend([parens(args), punct('.'), ident("await")]);
+        ts
+    }
+
+    /// `<sleep>(<duration>).await`
+    ///
+    /// ```text
+    /// ::tokio::time::sleep(<duration>).await
+    /// ::async_std::task::sleep(<duration>).await
+    /// ::async_io::Timer::after(<duration>).await
+    /// <path::to::MyTimer as ::tokio_timeout::Timer>::sleep(<duration>).await
+    /// ::embassy_time::Timer::after(::embassy_time::Duration::from_micros(
+    ///     ::tokio_timeout::__private::as_micros(<duration>))).await
+    /// ```
+    #[must_use]
+    pub(crate) fn sleep(&self, duration: TokenStream) -> TokenStream {
+        let mut ts = match self {
+            Runtime::Tokio(path) => tokio_path(path.as_ref(), &["time", "sleep"]),
+            Runtime::AsyncStd => abs_path(&["async_std", "task", "sleep"]),
+            Runtime::Smol => abs_path(&["async_io", "Timer", "after"]),
+            Runtime::Embassy => abs_path(&["embassy_time", "Timer", "after"]),
+            Runtime::Backend(path) => timer_fn(path, "sleep"),
+        };
+        let duration = if let Runtime::Embassy = self {
+            embassy_duration(duration, None)
+        } else {
+            duration
+        };
+        ts.extend([parens(duration), punct('.'), ident("await")]);
+        ts
+    }
+
+    /// `<Instant>::now()`, the start of a call for measuring how long it ran,
+    /// tokio's instant follows its paused clock in tests
+    ///
+    /// ```text
+    /// ::tokio::time::Instant::now()
+    /// ::std::time::Instant::now()
+    /// ::embassy_time::Instant::now()
+    /// <path::to::MyTimer as ::tokio_timeout::Timer>::now()
+    /// ```
+    #[must_use]
+    pub(crate) fn now(&self) -> TokenStream {
+        let mut ts = match self {
+            Runtime::Tokio(path) => tokio_path(path.as_ref(), &["time", "Instant", "now"]),
+            Runtime::Embassy => abs_path(&["embassy_time", "Instant", "now"]),
+            Runtime::AsyncStd | Runtime::Smol => abs_path(&["std", "time", "Instant", "now"]),
+            Runtime::Backend(path) => timer_fn(path, "now"),
+        };
+        ts.extend([parens(TokenStream::new())]);
+        ts
+    }
+
+    /// The time passed since `started`, bound to [`Runtime::now`]
+    ///
+    /// ```text
+    /// started.elapsed()
+    /// <path::to::MyTimer as ::tokio_timeout::Timer>::now().saturating_sub(started)
+    /// ::core::time::Duration::from_micros(started.elapsed().as_micros())
+    /// ```
+    #[must_use]
+    pub(crate) fn elapsed(&self, started: &Ident) -> TokenStream {
+        if let Runtime::Embassy = self {
+            let mut ts = abs_path(&["core", "time", "Duration", "from_micros"]);
+            ts.extend([parens(TokenStream::from_iter([
+                TokenTree::Ident(started.clone()),
+                punct('.'),
+                ident("elapsed"),
+                parens(TokenStream::new()),
+                punct('.'),
+                ident("as_micros"),
+                parens(TokenStream::new()),
+            ]))]);
+            return ts;
+        }
+        if let Runtime::Backend(_) = self {
+            let mut ts = self.now();
+            ts.extend([
+                punct('.'),
+                ident("saturating_sub"),
+                parens(TokenStream::from(TokenTree::Ident(started.clone()))),
+            ]);
+            return ts;
+        }
+        TokenStream::from_iter([
+            TokenTree::Ident(started.clone()),
+            punct('.'),
+            ident("elapsed"),
+            parens(TokenStream::new()),
+        ])
+    }
+}
+
+/// `<path>::<segments>`, the path defaulting to `::tokio`, or to
+/// `::tokio_timeout::__private::tokio` with the `tokio` feature
+fn tokio_path(path: Option<&TokenStream>, segments: &[&str]) -> TokenStream {
+    let mut ts = path.cloned().unwrap_or_else(|| {
+        if cfg!(feature = "tokio") {
+            abs_path(&["tokio_timeout", "__private", "tokio"])
+        } else {
+            abs_path(&["tokio"])
+        }
+    });
+    ts.extend(abs_path(segments));
+    ts
+}
+
+/// `::embassy_time::Duration::from_micros(<micros>)`, converted at expansion time if the duration
+/// is `known`. Rounds up to whole microseconds, so that a timeout never fires early.
+fn embassy_duration(duration: TokenStream, known: Option<Duration>) -> TokenStream {
+    let micros = if let Some(known) = known {
+        let micros = known.as_micros() + u128::from(known.subsec_nanos() % 1000 != 0);
+        let micros = u64::try_from(micros).unwrap_or(u64::MAX);
+        TokenStream::from(TokenTree::Literal(Literal::u64_unsuffixed(micros)))
+    } else {
+        let mut ts = abs_path(&["tokio_timeout", "__private", "as_micros"]);
+        ts.extend([parens(duration)]);
+        ts
+    };
+    let mut ts = abs_path(&["embassy_time", "Duration", "from_micros"]);
+    ts.extend([parens(micros)]);
+    ts
+}
+
+/// `<path::to::MyTimer as ::tokio_timeout::Timer>::<name>`
+fn timer_fn(path: &TokenStream, name: &str) -> TokenStream {
+    let mut qualified = path.clone();
+    qualified.extend([ident("as")]);
+    qualified.extend(abs_path(&["tokio_timeout", "Timer"]));
+    let mut ts = TokenStream::from_iter([punct('<')]);
+    ts.extend(qualified);
+    ts.extend([
+        punct('>'),
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        ident(name),
+    ]);
+    ts
+}
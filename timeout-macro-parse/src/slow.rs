@@ -0,0 +1,98 @@
+use crate::runtime::Runtime;
+use crate::tokens::{abs_path, braces, ident, internal_ident, let_binding, parens, punct};
+#[cfg(not(feature = "test"))]
+use proc_macro::{Literal, Punct, Spacing, TokenStream, TokenTree};
+#[cfg(feature = "test")]
+use proc_macro2::{Literal, Punct, Spacing, TokenStream, TokenTree};
+use std::time::Duration;
+use timeout_duration::HumanDuration;
+
+pub(crate) struct SlowWarning {
+    pub(crate) after: Duration,
+    pub(crate) on_slow: Option<TokenStream>,
+}
+
+impl SlowWarning {
+    /// Wraps the body so that `on_slow` fires once if it's still running after `after`,
+    /// letting it run on until the hard timeout.
+    ///
+    /// ```text
+    /// async {
+    ///     let mut body = ::core::pin::pin!(async { <body> });
+    ///     match ::tokio::time::timeout(<after>, body.as_mut()).await {
+    ///         ::core::result::Result::Ok(v) => v,
+    ///         ::core::result::Result::Err(_) => {
+    ///             on_slow("<message>");
+    ///             body.await
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub(crate) fn wrap(self, fn_name: &str, body: TokenStream, runtime: &Runtime) -> TokenStream {
+        let pinned = internal_ident("body");
+        let value = internal_ident("v");
+        let message = TokenTree::Literal(Literal::string(&format!(
+            "'{fn_name}' is slow, still running after {}",
+            HumanDuration(self.after)
+        )));
+        let mut pin = abs_path(&["core", "pin", "pin"]);
+        pin.extend([
+            punct('!'),
+            parens(TokenStream::from_iter([ident("async"), braces(body)])),
+        ]);
+        let mut inner = let_binding(&pinned, true, pin);
+
+        inner.extend([ident("match")]);
+        inner.extend(runtime.timeout(
+            crate::tokens::duration(self.after),
+            Some(self.after),
+            TokenStream::from_iter([
+                TokenTree::Ident(pinned.clone()),
+                punct('.'),
+                ident("as_mut"),
+                parens(TokenStream::new()),
+            ]),
+        ));
+
+        let mut on_slow = if let Some(handler) = self.on_slow {
+            let mut ts = crate::tokens::callee(handler);
+            ts.extend([parens(TokenStream::from(message))]);
+            ts
+        } else {
+            let mut ts = abs_path(&["std", "eprintln"]);
+            ts.extend([
+                punct('!'),
+                parens(TokenStream::from_iter([
+                    TokenTree::Literal(Literal::string("{}")),
+                    punct(','),
+                    message,
+                ])),
+            ]);
+            ts
+        };
+        on_slow.extend([
+            punct(';'),
+            TokenTree::Ident(pinned),
+            punct('.'),
+            ident("await"),
+        ]);
+        let mut arms = abs_path(&["core", "result", "Result", "Ok"]);
+        arms.extend([
+            parens(TokenStream::from(TokenTree::Ident(value.clone()))),
+            TokenTree::Punct(Punct::new('=', Spacing::Joint)),
+            TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+            TokenTree::Ident(value),
+            punct(','),
+        ]);
+        arms.extend(abs_path(&["core", "result", "Result", "Err"]));
+        arms.extend([
+            parens(TokenStream::from(ident("_"))),
+            TokenTree::Punct(Punct::new('=', Spacing::Joint)),
+            TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+            braces(on_slow),
+        ]);
+        inner.extend([braces(arms)]);
+        TokenStream::from_iter([ident("async"), braces(inner)])
+    }
+}
@@ -0,0 +1,83 @@
+//! Small helpers for building output tokens by hand, keeping the generated paths readable
+//! without pulling in `quote`.
+#[cfg(not(feature = "test"))]
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+#[cfg(feature = "test")]
+use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use std::time::Duration;
+
+pub(crate) fn ident(name: &str) -> TokenTree {
+    TokenTree::Ident(Ident::new(name, Span::call_site()))
+}
+
+/// An identifier for a binding introduced by the macro, invisible to the user's code
+pub(crate) fn internal_ident(name: &str) -> Ident {
+    Ident::new(name, Span::mixed_site())
+}
+
+pub(crate) fn punct(ch: char) -> TokenTree {
+    TokenTree::Punct(Punct::new(ch, Spacing::Alone))
+}
+
+pub(crate) fn parens(inner: TokenStream) -> TokenTree {
+    TokenTree::Group(Group::new(Delimiter::Parenthesis, inner))
+}
+
+pub(crate) fn braces(inner: TokenStream) -> TokenTree {
+    TokenTree::Group(Group::new(Delimiter::Brace, inner))
+}
+
+/// An absolute path, `::first::second::..`
+pub(crate) fn abs_path(segments: &[&str]) -> TokenStream {
+    let mut ts = TokenStream::new();
+    for segment in segments {
+        ts.extend([
+            TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+            TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            ident(segment),
+        ]);
+    }
+    ts
+}
+
+/// `let [mut] <name> = <value>;`
+pub(crate) fn let_binding(name: &Ident, mutable: bool, value: TokenStream) -> TokenStream {
+    let mut ts = TokenStream::from(ident("let"));
+    if mutable {
+        ts.extend([ident("mut")]);
+    }
+    ts.extend([
+        TokenTree::Ident(name.clone()),
+        TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+    ]);
+    ts.extend(value);
+    ts.extend([punct(';')]);
+    ts
+}
+
+/// `::core::time::Duration::new(<secs>, <nanos>)`
+pub(crate) fn duration(d: Duration) -> TokenStream {
+    let mut ts = abs_path(&["core", "time", "Duration", "new"]);
+    ts.extend([parens(TokenStream::from_iter([
+        TokenTree::Literal(Literal::u64_unsuffixed(d.as_secs())),
+        punct(','),
+        TokenTree::Literal(Literal::u32_suffixed(d.subsec_nanos())),
+    ]))]);
+    ts
+}
+
+/// Makes a user supplied expression safe to put in call position.
+/// Plain paths, including macro paths such as `anyhow::bail!`, are kept as is,
+/// anything else, like closures or method calls, is wrapped in parenthesis.
+pub(crate) fn callee(expr: TokenStream) -> TokenStream {
+    let is_path = expr.clone().into_iter().all(|t| match t {
+        TokenTree::Ident(_) => true,
+        TokenTree::Punct(p) => matches!(p.as_char(), ':' | '!'),
+        _ => false,
+    });
+    if is_path {
+        expr
+    } else {
+        TokenStream::from(TokenTree::Group(Group::new(Delimiter::Parenthesis, expr)))
+    }
+}
@@ -0,0 +1,9 @@
+#![warn(clippy::pedantic)]
+use proc_macro::TokenStream;
+
+/// The `timeout` attribute, it's meant to be used through the `tokio-timeout` crate,
+/// where it's documented, since the expanded code refers to items in that crate.
+#[proc_macro_attribute]
+pub fn timeout(attr: TokenStream, item: TokenStream) -> TokenStream {
+    timeout_macro_parse::tokio_timeout(attr, item)
+}
@@ -0,0 +1,66 @@
+//! Randomised timeouts for `jitter`, using a small thread-local generator rather than `rand`.
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// How far, in either direction, the effective timeout may stray from `duration`
+#[derive(Debug, Copy, Clone)]
+pub enum Jitter {
+    Fixed(Duration),
+    /// In percent of `duration`
+    Percent(u32),
+}
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Keeps the band below `2^63` nanoseconds, around 292 years, so that the width of the
+/// band always fits in a `u64`
+const MAX_BAND_NANOS: u128 = (u64::MAX / 2) as u128;
+
+thread_local! {
+    static STATE: Cell<u64> = Cell::new(seed());
+}
+
+/// Seeds from std's randomly keyed hasher, which is seeded from the OS once per thread
+fn seed() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0x9e37_79b9_7f4a_7c15);
+    // Xorshift can't leave the all zero state
+    hasher.finish() | 1
+}
+
+/// xorshift64*, plenty for spreading out timeouts
+fn next_u64() -> u64 {
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    })
+}
+
+/// A uniformly distributed value in `0..=max`, `max` has to fit in a `u64`
+fn uniform(max: u128) -> u128 {
+    (u128::from(next_u64()) * (max + 1)) >> 64
+}
+
+/// Picks a duration uniformly within `jitter` around `duration`, never below zero
+#[must_use]
+pub fn jitter(duration: Duration, jitter: Jitter) -> Duration {
+    let nanos = duration.as_nanos();
+    let band = match jitter {
+        Jitter::Fixed(band) => band.as_nanos(),
+        Jitter::Percent(percent) => nanos * u128::from(percent) / 100,
+    }
+    .min(MAX_BAND_NANOS);
+    let lower = nanos.saturating_sub(band);
+    let effective = lower + uniform(nanos + band - lower);
+    u64::try_from(effective / NANOS_PER_SEC).map_or(Duration::MAX, |secs| {
+        // Always less than a second of nanos
+        #[allow(clippy::cast_possible_truncation)]
+        Duration::new(secs, (effective % NANOS_PER_SEC) as u32)
+    })
+}
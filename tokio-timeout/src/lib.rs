@@ -1,5 +1,117 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(clippy::pedantic)]
-use proc_macro::TokenStream;
+#[cfg(feature = "std")]
+use std::fmt::Display;
+#[cfg(feature = "std")]
+use std::time::Duration;
+#[cfg(feature = "std")]
+pub use timeout_duration::{ParseDurationError, ParseDurationErrorKind};
+pub use timer::Timer;
+pub use types::{DurationClamp, Elapsed, TimeoutContext, TimeoutError};
+
+#[cfg(feature = "std")]
+mod jitter;
+mod timer;
+mod types;
+
+/// Parses a duration at runtime, with the same grammar as the `duration` attribute of [`timeout`],
+/// for durations coming from config files, CLI flags or environment variables.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// assert_eq!(Duration::from_secs(5400), tokio_timeout::parse_duration("1h30m").unwrap());
+/// assert_eq!(Duration::from_secs(5400), tokio_timeout::parse_duration("1 hour 30 minutes").unwrap());
+/// assert_eq!(Duration::from_millis(2500), tokio_timeout::parse_duration("PT2.5S").unwrap());
+/// let err = tokio_timeout::parse_duration("1z").unwrap_err();
+/// assert_eq!(tokio_timeout::ParseDurationErrorKind::UnknownUnit, err.kind());
+/// ```
+///
+/// # Errors
+/// If the string isn't a valid, non-zero, duration
+#[cfg(feature = "std")]
+pub fn parse_duration(dur: &str) -> Result<Duration, ParseDurationError> {
+    timeout_duration::parse_duration(dur)
+}
+
+/// Parses a timeout at runtime, like [`parse_duration`], but `"none"` or `"infinite"` give `None`,
+/// for a `duration` expression evaluating to an `Option<Duration>` where `None` means no timeout.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// assert_eq!(Some(Duration::from_secs(30)), tokio_timeout::parse_timeout("30s").unwrap());
+/// assert_eq!(None, tokio_timeout::parse_timeout("none").unwrap());
+/// assert_eq!(None, tokio_timeout::parse_timeout("infinite").unwrap());
+/// ```
+///
+/// # Errors
+/// If the string is neither `"none"`, `"infinite"`, nor a valid, non-zero, duration
+#[cfg(feature = "std")]
+pub fn parse_timeout(dur: &str) -> Result<Option<Duration>, ParseDurationError> {
+    timeout_duration::parse_timeout(dur)
+}
+
+/// Formats a duration normalised into days, hours, minutes and fractional seconds,
+/// or the largest fitting sub-second unit. The output parses back with [`parse_duration`].
+///
+/// ```
+/// use std::time::Duration;
+///
+/// assert_eq!("1h2m", tokio_timeout::format_duration(Duration::from_secs(3720)).to_string());
+/// assert_eq!("1.5s", tokio_timeout::format_duration(Duration::from_millis(1500)).to_string());
+/// assert_eq!("250us", tokio_timeout::format_duration(Duration::from_micros(250)).to_string());
+/// ```
+#[must_use]
+#[cfg(feature = "std")]
+pub fn format_duration(dur: Duration) -> impl Display {
+    timeout_duration::HumanDuration(dur)
+}
+
+/// Used by the expanded code, not public API
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "std")]
+    pub use crate::jitter::{jitter, Jitter};
+    pub use crate::timer::{race, timeout};
+    use core::time::Duration;
+    /// The default tokio path with the `tokio` feature
+    #[cfg(feature = "tokio")]
+    pub use tokio;
+
+    /// The error of a timed out call on runtimes without their own timeout error
+    #[must_use]
+    pub const fn elapsed() -> crate::Elapsed {
+        crate::Elapsed { _private: () }
+    }
+
+    /// Reads a timeout from the environment variable `var`, falling back to `default`
+    /// if it's unset or can't be parsed. `None` is no timeout.
+    #[must_use]
+    #[cfg(feature = "std")]
+    pub fn duration_from_env(var: &str, default: Option<Duration>) -> Option<Duration> {
+        let Ok(value) = std::env::var(var) else {
+            return default;
+        };
+        crate::parse_timeout(&value).unwrap_or(default)
+    }
+
+    /// The timeout message with the effective timeout appended, for `log` and `on_error`
+    /// handlers when `jitter`, `min` or `max` make it differ from the configured duration
+    #[must_use]
+    #[cfg(feature = "std")]
+    pub fn effective_message(message: &str, duration: Duration) -> String {
+        format!("{message}, effective timeout {duration:?}")
+    }
+
+    /// Microseconds in `duration`, for timers counting in microseconds. Rounds up, so that
+    /// a timeout never fires early, and saturates.
+    #[must_use]
+    pub fn as_micros(duration: Duration) -> u64 {
+        let micros = duration.as_micros() + u128::from(duration.subsec_nanos() % 1000 != 0);
+        u64::try_from(micros).unwrap_or(u64::MAX)
+    }
+}
 
 /// # Timeout macro
 ///
@@ -18,8 +130,11 @@ use proc_macro::TokenStream;
 /// ## Duration
 ///
 /// 'Duration' can be either a string-literal that specifies a duration,
-/// valid values are `<n>h` for hours, `<n>m` for minutes, `<n>s` for seconds, and `<n>ms`
-/// for milliseconds. They can be chained together.
+/// valid values are `<n>w` for weeks, `<n>d` for days, `<n>h` for hours, `<n>m` for minutes,
+/// `<n>s` for seconds, `<n>ms` for milliseconds, `<n>us` or `<n>µs` for microseconds, and `<n>ns`
+/// for nanoseconds. They can be chained together, optionally separated by whitespace.
+/// The long unit names of [humantime](https://docs.rs/humantime), like `hours`, `hr`, `mins`,
+/// `second`, `secs` or `millis`, are accepted as well, as are months (`M`) and years (`y`).
 ///
 /// ```
 /// #[tokio_timeout::timeout(duration = "5h4m3s2ms", on_error = "panic")]
@@ -28,7 +143,36 @@ use proc_macro::TokenStream;
 /// }
 /// ```
 ///
-/// Duration can also be specified to be some constant
+/// Values can have a fractional part, which is converted exactly into nanoseconds,
+/// a value more precise than that is a compile error.
+///
+/// ```
+/// #[tokio_timeout::timeout(duration = "1.5s", on_error = "panic")]
+/// async fn my_fractional_fn() {
+///     println!("hello!");
+/// }
+/// ```
+///
+/// ```
+/// #[tokio_timeout::timeout(duration = "1 hour 30 minutes", on_error = "panic")]
+/// async fn my_long_form_fn() {
+///     println!("hello!");
+/// }
+/// ```
+///
+/// ISO-8601 durations, `P[nW][nD][T[nH][nM][nS]]`, are detected by their leading `P`.
+/// Years and months are rejected, since their length depends on the calendar.
+///
+/// ```
+/// #[tokio_timeout::timeout(duration = "PT2.5S", on_error = "panic")]
+/// async fn my_iso_fn() {
+///     println!("hello!");
+/// }
+/// ```
+///
+/// Duration can also be specified to be some constant, or any other expression evaluating to a
+/// `Duration`, up until the comma starting the next attribute. The expression is evaluated at the start of each call,
+/// so it can refer to the function's arguments, or `self`.
 ///
 /// ```
 /// use std::time::Duration;
@@ -41,12 +185,105 @@ use proc_macro::TokenStream;
 /// }
 /// ```
 ///
+/// ```
+/// use std::time::Duration;
+///
+/// pub struct Client {
+///     timeout: Duration,
+/// }
+///
+/// impl Client {
+///     #[tokio_timeout::timeout(duration = self.timeout, on_error = "panic")]
+///     async fn call(&self) {}
+/// }
+///
+/// #[tokio_timeout::timeout(duration = Duration::from_millis(timeout_ms), on_error = "panic")]
+/// async fn my_fn(timeout_ms: u64) {}
+/// ```
+///
+/// The duration can also be read from an environment variable with `duration_env`, in which case
+/// `duration` has to be a literal, used when the variable is unset or can't be parsed.
+/// The variable is read once, on the first call, with the same grammar as the `duration` literal.
+///
+/// ```
+/// #[tokio_timeout::timeout(duration_env = "PAYMENTS_TIMEOUT", duration = "5s", on_error = "panic")]
+/// async fn my_fn() {
+///     println!("hello!");
+/// }
+/// ```
+///
+/// ```compile_fail
+/// const MY_DUR: std::time::Duration = std::time::Duration::from_millis(55);
+///
+/// #[tokio_timeout::timeout(duration_env = "PAYMENTS_TIMEOUT", duration = MY_DUR, on_error = "panic")]
+/// async fn env_default_not_literal() {}
+/// ```
+///
+/// Durations are summed up, so `"1m1m"` is two minutes. The `strict` flag rejects duration
+/// literals where a unit is repeated or comes after a smaller one, catching typos such as `"1m1m"`
+/// meant as `"1m1s"`.
+///
+/// ```
+/// #[tokio_timeout::timeout(duration = "1h30m", on_error = "panic", strict)]
+/// async fn my_strict_fn() {
+///     println!("hello!");
+/// }
+/// ```
+///
+/// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "1m1m", on_error = "panic", strict)]
+/// async fn repeated_unit() {}
+/// ```
+///
+/// A `duration` of `"none"` or `"infinite"` never times out, the body is awaited directly.
+/// An expression may evaluate to an `Option<Duration>` instead of a `Duration`, `None` skipping the
+/// timeout for that call, and `duration_env` accepts `"none"` as well, for example to turn timeouts
+/// off while debugging. `tokio_timeout::parse_timeout` parses such optional durations at runtime.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// #[tokio_timeout::timeout(duration = "none", on_error = "panic")]
+/// async fn my_unbounded_fn() {
+///     println!("hello!");
+/// }
+///
+/// #[tokio_timeout::timeout(duration = timeout, on_error = "panic")]
+/// async fn my_optional_fn(timeout: Option<Duration>) {
+///     println!("hello!");
+/// }
+/// ```
+///
 /// ## On error
 ///
 /// On error can either be the string literal "panic", as seen in examples above,
+/// the string literal "default", which makes the function return `Default::default()` on timeout,
+/// the string literal "into", which makes the function return `Err(TimeoutError.into())` on timeout,
 /// or something that can be invoked with a `&'static str` to produce an error.
 ///
 /// ```
+/// #[tokio_timeout::timeout(duration = "5h4m3s2ms", on_error = "default")]
+/// async fn my_fn_empty_on_timeout() -> Vec<u8> {
+///     vec![1, 2, 3]
+/// }
+/// ```
+///
+/// The "into" mode works for any `Result<_, E>` where `E: From<`[`TimeoutError`]`>`,
+/// such as `std::io::Error` or `anyhow::Error`.
+///
+/// ```
+/// #[tokio_timeout::timeout(duration = "5h4m3s2ms", on_error = "into")]
+/// async fn my_io_fn() -> std::io::Result<()> {
+///     Ok(())
+/// }
+///
+/// #[tokio_timeout::timeout(duration = "5h4m3s2ms", on_error = "into")]
+/// async fn my_anyhow_fn() -> anyhow::Result<()> {
+///     Ok(())
+/// }
+/// ```
+///
+/// ```
 ///
 /// fn to_error_result(s: &str) -> Result<(), String>{
 ///    Err(s.to_string())
@@ -89,6 +326,314 @@ use proc_macro::TokenStream;
 ///
 /// ```
 ///
+/// Any expression up until the comma starting the next attribute, `<name> =` or `strict`,
+/// can be used, closures included. Commas in generics or closure parameters, such as in
+/// `|msg: &'static str| -> Result<u32, String> { .. }`, are part of the expression.
+/// If it isn't a plain path it's wrapped in parenthesis and invoked with the `&'static str`.
+///
+/// ```
+/// pub enum MyErr {
+///     Timeout(String)
+/// }
+///
+/// #[tokio_timeout::timeout(duration = "5h4m3s2ms", on_error = |msg: &str| Err(MyErr::Timeout(msg.into())))]
+/// async fn my_closure_err_fn() -> Result<(), MyErr> {
+///     Ok(())
+/// }
+///
+/// fn make_handler(prefix: &'static str) -> impl Fn(&'static str) -> Result<(), String> {
+///     move |msg| Err(format!("{prefix}: {msg}"))
+/// }
+///
+/// #[tokio_timeout::timeout(duration = "5h4m3s2ms", on_error = make_handler("upstream"))]
+/// async fn my_constructed_err_fn() -> Result<(), String> {
+///     Ok(())
+/// }
+/// ```
+///
+/// ## On error ctx
+///
+/// Instead of `on_error`, `on_error_ctx` can be supplied with something that can be invoked
+/// with a [`TimeoutContext`], which carries the function name, `module_path!()`, `file!()`, `line!()`,
+/// the configured duration, the measured elapsed time, and the timer's error.
+/// `on_error` and `on_error_ctx` are mutually exclusive.
+///
+/// ```
+/// use tokio::time::error::Elapsed;
+/// use tokio_timeout::TimeoutContext;
+///
+/// pub enum MyErr {
+///     Timeout { fn_name: &'static str, line: u32 }
+/// }
+///
+/// fn to_my_err(ctx: TimeoutContext<Elapsed>) -> Result<(), MyErr> {
+///     Err(MyErr::Timeout { fn_name: ctx.fn_name, line: ctx.line })
+/// }
+///
+/// #[tokio_timeout::timeout(duration = "5h4m3s2ms", on_error_ctx = to_my_err)]
+/// async fn my_ctx_fn() -> Result<(), MyErr> {
+///     Ok(())
+/// }
+/// ```
+///
+/// ```compile_fail
+/// fn print_err(s: &'static str) {}
+///
+/// #[tokio_timeout::timeout(duration = "1s", on_error = "panic", on_error_ctx = print_err)]
+/// async fn mutually_exclusive() {}
+/// ```
+///
+/// ## Fallback
+///
+/// Instead of `on_error`, `fallback` can be supplied with an expression that's evaluated in the
+/// function's scope, only when the timeout fires, to produce the return value.
+/// `on_error`, `on_error_ctx`, and `fallback` are mutually exclusive.
+///
+/// ```
+/// pub struct Cache {
+///     cached: Vec<u8>,
+/// }
+///
+/// impl Cache {
+///     #[tokio_timeout::timeout(duration = "5h4m3s2ms", fallback = self.cached.clone())]
+///     async fn fetch(&self) -> Vec<u8> {
+///         vec![1, 2, 3]
+///     }
+/// }
+///
+/// #[tokio_timeout::timeout(duration = "5h4m3s2ms", fallback = Vec::new())]
+/// async fn my_fallback_fn() -> Vec<u8> {
+///     vec![1, 2, 3]
+/// }
+/// ```
+///
+/// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "1s", on_error = "default", fallback = Vec::new())]
+/// async fn mutually_exclusive() -> Vec<u8> {
+///     vec![]
+/// }
+/// ```
+///
+/// ## Log
+///
+/// An optional `log` can be supplied, which is invoked with the same `&'static str` as `on_error`
+/// before the `on_error` outcome, useful for leaving a trace when falling back to a default.
+///
+/// ```
+/// fn print_err(s: &'static str) {
+///     eprintln!("oh no: {s}")
+/// }
+///
+/// #[tokio_timeout::timeout(duration = "5h4m3s2ms", on_error = "default", log = print_err)]
+/// async fn my_logged_fn() -> Option<u8> {
+///     Some(1)
+/// }
+/// ```
+///
+/// ## Message
+///
+/// The message passed to `on_error` and `log` defaults to `'<fn>' timed out after <duration>`,
+/// with the duration formatted like `1.5s` or `1h2m`. A `message` template replaces it,
+/// with the placeholders `{fn}`, `{module}`, `{duration}` and `{location}`, the file and line of the
+/// attribute. `{{` and `}}` are literal braces, any other placeholder is a compile error.
+/// The message stays a `&'static str`.
+///
+/// ```
+/// fn print_err(s: &'static str) {
+///     eprintln!("{s}")
+/// }
+///
+/// #[tokio_timeout::timeout(duration = "1.5s", on_error = "default", log = print_err, message = "upstream {fn} exceeded {duration} at {location}")]
+/// async fn my_messaged_fn() -> Option<u8> {
+///     Some(1)
+/// }
+/// ```
+///
+/// ## Clamping
+///
+/// When the duration is evaluated at runtime, `min = "<duration>"` and `max = "<duration>"` clamp it
+/// before it's used, so that a misconfigured `0` or `u64::MAX` doesn't fail every call instantly or
+/// never time out. An optional `on_clamp` handler is invoked with a `tokio_timeout::DurationClamp`
+/// whenever the duration had to be clamped, so bad configuration gets noticed.
+/// With `jitter`, the bounds apply to the jittered duration, so the effective timeout always stays
+/// within `min` and `max`. As with `jitter`, the clamped timeout is appended to the message.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// fn warn_clamp(clamp: tokio_timeout::DurationClamp) {
+///     eprintln!("{clamp}");
+/// }
+///
+/// #[tokio_timeout::timeout(duration = Duration::from_millis(timeout_ms), on_error = "panic", min = "10ms", max = "30s", on_clamp = warn_clamp)]
+/// async fn my_clamped_fn(timeout_ms: u64) {}
+/// ```
+///
+/// ## Jitter
+///
+/// `jitter = "<duration>"` or `jitter = "<n>%"` randomises each call's timeout uniformly within that
+/// band around `duration`, avoiding synchronised retries across many instances. The randomness comes
+/// from a cheap thread-local generator. The timeout message names the band with the effective
+/// timeout appended, such as `'my_fn' timed out after 1s ±10%, effective timeout 1.04s`, while
+/// `on_error = "into"` and `on_error_ctx` report it as `TimeoutError::duration()` and
+/// `TimeoutContext::duration`. The message is formatted when the timeout fires, so `log` and
+/// `on_error` handlers are given a `&str` that only lives for the call and have to take `&str`
+/// rather than `&'static str`. The embassy runtime has no `std` to format with, its messages name
+/// the configured duration only.
+///
+/// ```
+/// #[tokio_timeout::timeout(duration = "1s", on_error = "into", jitter = "10%")]
+/// async fn my_jittered_fn() -> Result<(), tokio_timeout::TimeoutError> {
+///     Ok(())
+/// }
+/// ```
+///
+/// ## Retries
+///
+/// `retries = <n>` re-runs the function body, under a fresh timeout, up to `n` more times
+/// when it times out. `on_error` is only invoked when all attempts have timed out,
+/// with a message reporting the number of attempts.
+///
+/// Optionally, `backoff = "<duration>"` sleeps between attempts, with `backoff_strategy`
+/// being either `"fixed"` (the default), or `"exponential"`, doubling the sleep for each attempt.
+/// `total = "<duration>"` caps the time spent over all attempts, cutting the last attempt short
+/// if necessary.
+///
+/// Since the body is re-run, each attempt gets its own clone of the arguments taken by value,
+/// `name: Type` where `Type` isn't a reference, so that the body can consume them. Those arguments
+/// have to implement `Clone`, otherwise take them by reference. `self` taken by value, and arguments
+/// bound with a pattern, such as `(a, b): (u32, u32)`, aren't cloned and can't be moved out of.
+///
+/// ```
+/// #[tokio_timeout::timeout(duration = "1s", on_error = "into", retries = 3, backoff = "100ms", backoff_strategy = "exponential", total = "5s")]
+/// async fn my_idempotent_fn(key: &str) -> std::io::Result<String> {
+///     Ok(key.to_string())
+/// }
+/// ```
+///
+/// ## Slow warnings
+///
+/// `warn_after = "<duration>"` fires a warning once if the body is still running after that
+/// duration, letting it run on until the hard timeout. By default the warning is printed with
+/// `eprintln!`, `on_slow` can be supplied with something that can be invoked with a `&'static str`
+/// to handle it instead, such as a function that logs through `tracing::warn!`.
+///
+/// ```
+/// fn log_slow(msg: &'static str) {
+///     eprintln!("degraded: {msg}");
+/// }
+///
+/// #[tokio_timeout::timeout(duration = "5s", on_error = "panic", warn_after = "500ms", on_slow = log_slow)]
+/// async fn my_sometimes_slow_fn() {
+///     println!("hello!");
+/// }
+/// ```
+///
+/// ## Runtime
+///
+/// The timer comes from tokio by default. `runtime = "async-std"` expands to
+/// `async_std::future::timeout` instead, with the same messages and `on_error` handling,
+/// `on_error_ctx` receiving async-std's `TimeoutError`. `runtime = "smol"` races the body against
+/// `async_io::Timer::after`, the timer smol re-exports, so `async-io` has to be a dependency.
+/// `on_error_ctx` receives a `tokio_timeout::Elapsed` for it. Backoff between retries sleeps on the
+/// same runtime. `runtime = "async-std"` and `runtime = "smol"` are only available with the
+/// `async-std` and `smol` features of `tokio-timeout`. Features only make a runtime available,
+/// the runtime is always picked by the attribute, so enabling a feature anywhere in the build never
+/// changes what other crates' functions expand to.
+///
+/// ```ignore
+/// #[tokio_timeout::timeout(duration = "5s", on_error = "panic", runtime = "async-std")]
+/// async fn my_async_std_fn() {
+///     println!("hello!");
+/// }
+///
+/// #[tokio_timeout::timeout(duration = "5s", on_error = "panic", runtime = "smol")]
+/// async fn my_smol_fn() {
+///     println!("hello!");
+/// }
+/// ```
+///
+/// tokio is referred to by the absolute path `::tokio`, so a local `mod tokio` can't shadow it.
+/// When tokio is renamed, or only reachable through a facade crate re-exporting it,
+/// `crate = ::our_rt::tokio` points the expanded code at it.
+///
+/// ```ignore
+/// #[tokio_timeout::timeout(duration = "5s", on_error = "panic", crate = ::our_rt::tokio)]
+/// async fn my_fn() {
+///     println!("hello!");
+/// }
+/// ```
+///
+/// With the `tokio` feature, `tokio-timeout` depends on tokio itself and the expanded code uses
+/// its re-export wherever there's no `crate` attribute, so a crate doesn't need its own tokio
+/// dependency. Features are unified across the build, so this applies to every crate using
+/// `tokio-timeout` by its own name, and the re-export is the same tokio as a direct dependency
+/// on tokio 1.
+///
+/// ```toml
+/// [dependencies]
+/// tokio-timeout = { version = "0.1", features = ["tokio"] }
+/// ```
+///
+/// `runtime = "embassy"` expands to `embassy_time::with_timeout` for firmware, with literal
+/// durations converted to `embassy_time::Duration` at expansion time, rounded up to whole
+/// microseconds. Messages are built with `concat!` and never reference `std`, with
+/// `default-features = false` on `tokio-timeout` the crate is `no_std`. `duration_env`, `jitter`
+/// and `warn_after` without `on_slow` need `std`, and are compile errors with embassy.
+///
+/// ```ignore
+/// #[tokio_timeout::timeout(duration = "50ms", on_error = "panic", runtime = "embassy")]
+/// async fn my_embassy_fn() {
+///     read_sensor().await;
+/// }
+/// ```
+///
+/// Any other timer can be plugged in with `backend = path::to::MyTimer`, naming a type that
+/// implements `tokio_timeout::Timer`, a sleep future factory plus a `now()`, for in-house
+/// executors, simulated clocks or timer crates such as `futures-timer`. The body is raced against
+/// the timer's sleep, `on_error_ctx` receiving a `tokio_timeout::Elapsed`.
+///
+/// ```ignore
+/// use std::time::Duration;
+///
+/// pub struct FuturesTimer;
+///
+/// impl tokio_timeout::Timer for FuturesTimer {
+///     type Sleep = futures_timer::Delay;
+///
+///     fn sleep(duration: Duration) -> Self::Sleep {
+///         futures_timer::Delay::new(duration)
+///     }
+///
+///     fn now() -> Duration {
+///         static START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+///         START.get_or_init(std::time::Instant::now).elapsed()
+///     }
+/// }
+///
+/// #[tokio_timeout::timeout(duration = "5s", on_error = "panic", backend = FuturesTimer)]
+/// async fn my_fn() {
+///     println!("hello!");
+/// }
+/// ```
+///
+/// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "1s", on_error = "panic", warn_after = "2s")]
+/// async fn warn_after_past_timeout() {}
+/// ```
+///
+/// ```compile_fail
+/// pub struct NotClone;
+///
+/// #[tokio_timeout::timeout(duration = "1s", on_error = "panic", retries = 1)]
+/// async fn retried_by_value_not_clone(arg: NotClone) {}
+/// ```
+///
+/// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "1s", on_error = "panic", backoff = "100ms")]
+/// async fn backoff_without_retries() {}
+/// ```
+///
 /// ```compile_fail
 /// #[tokio_timeout::timeout]
 /// async fn both_attrs_needed() {}
@@ -105,11 +650,70 @@ use proc_macro::TokenStream;
 /// ```
 ///
 /// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "1.0000000001s", on_error = "panic")]
+/// async fn sub_nanosecond_duration() {}
+/// ```
+///
+/// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "18446744073709551615w", on_error = "panic")]
+/// async fn overflowing_duration() {}
+/// ```
+///
+/// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "P1M", on_error = "panic")]
+/// async fn calendar_dependent_duration() {}
+/// ```
+///
+/// ```compile_fail
 /// #[tokio_timeout::timeout(duration = "1s", on_error = "panico")]
 /// async fn unrecognized_on_error() {}
 /// ```
 ///
-#[proc_macro_attribute]
-pub fn timeout(attr: TokenStream, item: TokenStream) -> TokenStream {
-    timeout_macro_parse::tokio_timeout(attr, item)
-}
+/// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "1s", on_error = "panic", message = "{fn} took {elapsed}")]
+/// async fn unknown_message_placeholder() {}
+/// ```
+///
+/// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "1s", on_error = "panic", jitter = "1s")]
+/// async fn jitter_as_long_as_duration() {}
+/// ```
+///
+/// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "1m", on_error = "panic", min = "10ms", max = "30s")]
+/// async fn duration_outside_bounds() {}
+/// ```
+///
+/// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "none", on_error = "panic", jitter = "10%")]
+/// async fn jitter_without_timeout() {}
+/// ```
+///
+/// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "1s", on_error = "panic", runtime = "glommio")]
+/// async fn unknown_runtime() {}
+/// ```
+///
+/// ```compile_fail
+/// pub struct MyTimer;
+///
+/// #[tokio_timeout::timeout(duration = "1s", on_error = "panic", runtime = "tokio", backend = MyTimer)]
+/// async fn runtime_and_backend() {}
+/// ```
+///
+/// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "1s", duration_env = "MY_TIMEOUT", on_error = "panic", runtime = "embassy")]
+/// async fn embassy_without_std() {}
+/// ```
+///
+/// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "1s", on_error = "panic", crate = ::not_tokio)]
+/// async fn crate_not_found() {}
+/// ```
+///
+/// ```compile_fail
+/// #[tokio_timeout::timeout(duration = "1s", on_error = "panic", runtime = "smol", crate = ::tokio)]
+/// async fn crate_with_other_runtime() {}
+/// ```
+///
+pub use tokio_timeout_macro::timeout;
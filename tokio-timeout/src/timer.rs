@@ -0,0 +1,77 @@
+//! The timer behind `backend = path::to::MyTimer`, for runtimes without built-in support.
+use crate::Elapsed;
+use core::future::Future;
+use core::pin::pin;
+use core::task::Poll;
+use core::time::Duration;
+
+/// A timer for the `backend` attribute of [`timeout`](crate::timeout), letting the timeout
+/// run on in-house executors, simulated clocks, or a timer crate such as `futures-timer`.
+///
+/// The body is raced against [`Timer::sleep`], timed out calls produce an [`Elapsed`] error.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// pub struct TokioTimer;
+///
+/// impl tokio_timeout::Timer for TokioTimer {
+///     type Sleep = tokio::time::Sleep;
+///
+///     fn sleep(duration: Duration) -> Self::Sleep {
+///         tokio::time::sleep(duration)
+///     }
+///
+///     fn now() -> Duration {
+///         static START: std::sync::OnceLock<tokio::time::Instant> = std::sync::OnceLock::new();
+///         START.get_or_init(tokio::time::Instant::now).elapsed()
+///     }
+/// }
+///
+/// #[tokio_timeout::timeout(duration = "5s", on_error = "panic", backend = TokioTimer)]
+/// async fn my_fn() {
+///     println!("hello!");
+/// }
+/// ```
+pub trait Timer {
+    /// The future returned by [`Timer::sleep`]
+    type Sleep: Future<Output = ()>;
+
+    /// A future completing once `duration` has passed
+    fn sleep(duration: Duration) -> Self::Sleep;
+
+    /// The current time, as the time passed since some fixed point, used to measure how
+    /// long a call has been running for `on_error_ctx` and the `total` retry budget
+    fn now() -> Duration;
+}
+
+/// Runs `fut` to completion, unless `T`'s sleep completes first, the body winning ties
+///
+/// # Errors
+/// If `duration` passes before `fut` completes
+pub async fn timeout<T: Timer, F: Future>(
+    duration: Duration,
+    fut: F,
+) -> Result<F::Output, Elapsed> {
+    race(T::sleep(duration), fut).await
+}
+
+/// Runs `fut` to completion, unless `sleep` completes first, the body winning ties.
+/// Used directly for timers without a [`Timer`] implementation, such as async-io's.
+///
+/// # Errors
+/// If `sleep` completes before `fut`
+pub async fn race<S: Future, F: Future>(sleep: S, fut: F) -> Result<F::Output, Elapsed> {
+    let mut fut = pin!(fut);
+    let mut sleep = pin!(sleep);
+    core::future::poll_fn(|cx| {
+        if let Poll::Ready(value) = fut.as_mut().poll(cx) {
+            return Poll::Ready(Ok(value));
+        }
+        sleep
+            .as_mut()
+            .poll(cx)
+            .map(|_| Err(crate::__private::elapsed()))
+    })
+    .await
+}
@@ -0,0 +1,161 @@
+use core::fmt::{Display, Formatter};
+use core::time::Duration;
+
+/// The error produced by `on_error = "into"` when a function times out.
+///
+/// With the `std` feature it converts into an [`std::io::Error`] of kind
+/// [`std::io::ErrorKind::TimedOut`], and, since it implements [`std::error::Error`],
+/// into error types such as `anyhow::Error`.
+///
+/// ```
+/// use std::time::Duration;
+/// use tokio_timeout::TimeoutError;
+///
+/// let err = TimeoutError::new("my_fn", Duration::from_millis(5));
+/// assert_eq!("my_fn", err.fn_name());
+/// let io: std::io::Error = err.into();
+/// assert_eq!(std::io::ErrorKind::TimedOut, io.kind());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimeoutError {
+    fn_name: &'static str,
+    duration: Duration,
+}
+
+impl TimeoutError {
+    #[must_use]
+    pub const fn new(fn_name: &'static str, duration: Duration) -> Self {
+        Self { fn_name, duration }
+    }
+
+    /// The name of the function that timed out
+    #[must_use]
+    pub const fn fn_name(&self) -> &'static str {
+        self.fn_name
+    }
+
+    /// The duration that the function was allowed to run for
+    #[must_use]
+    pub const fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl Display for TimeoutError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "'{}' timed out after {:?}", self.fn_name, self.duration)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TimeoutError {}
+
+#[cfg(feature = "std")]
+impl From<TimeoutError> for std::io::Error {
+    fn from(value: TimeoutError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::TimedOut, value)
+    }
+}
+
+/// What `on_error_ctx` handlers are invoked with when a function times out.
+///
+/// `E` is the error produced by the timer, `tokio::time::error::Elapsed` for tokio,
+/// `async_std::future::TimeoutError` for async-std and [`Elapsed`] for smol or a `backend`.
+///
+/// ```
+/// use tokio::time::error::Elapsed;
+/// use tokio_timeout::TimeoutContext;
+///
+/// fn to_error(ctx: TimeoutContext<Elapsed>) -> Result<(), String> {
+///     Err(format!("{}::{} timed out after {:?}", ctx.module_path, ctx.fn_name, ctx.elapsed))
+/// }
+///
+/// #[tokio_timeout::timeout(duration = "1s", on_error_ctx = to_error)]
+/// async fn my_fn() -> Result<(), String> {
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TimeoutContext<E> {
+    /// The name of the function that timed out
+    pub fn_name: &'static str,
+    /// The `module_path!()` of the function that timed out
+    pub module_path: &'static str,
+    /// The `file!()` of the function that timed out
+    pub file: &'static str,
+    /// The `line!()` of the `timeout` attribute
+    pub line: u32,
+    /// The duration that the function was allowed to run for
+    pub duration: Duration,
+    /// The time that passed from the start of the call until it timed out
+    pub elapsed: Duration,
+    /// The error produced by the timer
+    pub error: E,
+}
+
+impl<E> Display for TimeoutContext<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "'{}::{}' ({}:{}) timed out after {:?}, configured timeout was {:?}",
+            self.module_path, self.fn_name, self.file, self.line, self.elapsed, self.duration
+        )
+    }
+}
+
+impl<E> From<TimeoutContext<E>> for TimeoutError {
+    fn from(value: TimeoutContext<E>) -> Self {
+        TimeoutError::new(value.fn_name, value.duration)
+    }
+}
+
+/// The error produced by the timer on runtimes that don't have a timeout error of their own,
+/// passed to `on_error_ctx` handlers as [`TimeoutContext::error`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Elapsed {
+    pub(crate) _private: (),
+}
+
+impl Display for Elapsed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("deadline has elapsed")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Elapsed {}
+
+/// What `on_clamp` handlers are invoked with when a duration is outside `min`/`max`.
+///
+/// ```
+/// use std::time::Duration;
+/// use tokio_timeout::DurationClamp;
+///
+/// fn warn_clamp(clamp: DurationClamp) {
+///     eprintln!("{clamp}");
+/// }
+///
+/// #[tokio_timeout::timeout(duration = Duration::from_secs(secs), on_error = "panic", min = "10ms", max = "30s", on_clamp = warn_clamp)]
+/// async fn my_fn(secs: u64) {}
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DurationClamp {
+    /// The name of the function whose duration was clamped
+    pub fn_name: &'static str,
+    /// The `module_path!()` of the function whose duration was clamped
+    pub module_path: &'static str,
+    /// The duration as evaluated
+    pub configured: Duration,
+    /// The duration used instead
+    pub clamped: Duration,
+}
+
+impl Display for DurationClamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "'{}::{}' duration {:?} is out of bounds, clamped to {:?}",
+            self.module_path, self.fn_name, self.configured, self.clamped
+        )
+    }
+}
@@ -0,0 +1,90 @@
+//! The `runtime = "async-std"` expansion, run against the real `async_std::future::timeout`
+//! on async-std's executor.
+use std::time::Duration;
+use tokio_timeout::timeout;
+
+#[timeout(duration = "1s", on_error = "panic", runtime = "async-std")]
+pub async fn my_fast_fn() -> u32 {
+    1
+}
+
+#[timeout(duration = "1ms", on_error = "panic", runtime = "async-std")]
+pub async fn my_panicking_fn() {
+    async_std::task::sleep(Duration::from_millis(1000)).await;
+}
+
+#[timeout(duration = "1ms", on_error = "into", runtime = "async-std")]
+pub async fn my_into_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    async_std::task::sleep(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+fn ctx_err(
+    ctx: tokio_timeout::TimeoutContext<async_std::future::TimeoutError>,
+) -> Result<(), tokio_timeout::TimeoutContext<async_std::future::TimeoutError>> {
+    Err(ctx)
+}
+
+#[timeout(duration = "1ms", on_error_ctx = ctx_err, runtime = "async-std")]
+pub async fn my_ctx_fn(
+) -> Result<(), tokio_timeout::TimeoutContext<async_std::future::TimeoutError>> {
+    async_std::task::sleep(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+static FLAKY_CALLS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+#[timeout(
+    duration = "5ms",
+    on_error = "panic",
+    retries = 2,
+    backoff = "1ms",
+    runtime = "async-std"
+)]
+pub async fn my_flaky_fn() -> u32 {
+    let call = FLAKY_CALLS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+    if call < 3 {
+        async_std::task::sleep(Duration::from_millis(1000)).await;
+    }
+    call
+}
+
+static SLOW_WARNINGS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+fn count_slow(_msg: &'static str) {
+    SLOW_WARNINGS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+}
+
+#[timeout(duration = "1s", on_error = "panic", warn_after = "1ms", on_slow = count_slow, runtime = "async-std")]
+pub async fn my_slow_fn() -> u32 {
+    async_std::task::sleep(Duration::from_millis(10)).await;
+    4
+}
+
+#[async_std::test]
+async fn completes_in_time() {
+    assert_eq!(1, my_fast_fn().await);
+}
+
+#[async_std::test]
+#[should_panic(expected = "'my_panicking_fn' timed out after 1ms")]
+async fn panics_on_timeout() {
+    my_panicking_fn().await;
+}
+
+#[async_std::test]
+async fn errors_on_timeout() {
+    let err = my_into_fn().await.unwrap_err();
+    assert_eq!("my_into_fn", err.fn_name());
+    assert_eq!(Duration::from_millis(1), err.duration());
+    let ctx = my_ctx_fn().await.unwrap_err();
+    assert_eq!("my_ctx_fn", ctx.fn_name);
+    assert!(ctx.elapsed >= Duration::from_millis(1));
+}
+
+#[async_std::test]
+async fn retries_and_warns() {
+    assert_eq!(3, my_flaky_fn().await);
+    assert_eq!(4, my_slow_fn().await);
+    assert_eq!(1, SLOW_WARNINGS.load(std::sync::atomic::Ordering::SeqCst));
+}
@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use tokio_timeout::timeout;
+
+static SLEEPS: AtomicU32 = AtomicU32::new(0);
+
+pub struct TokioTimer;
+
+impl tokio_timeout::Timer for TokioTimer {
+    type Sleep = tokio::time::Sleep;
+
+    fn sleep(duration: Duration) -> Self::Sleep {
+        tokio::time::sleep(duration)
+    }
+
+    fn now() -> Duration {
+        static START: std::sync::OnceLock<tokio::time::Instant> = std::sync::OnceLock::new();
+        START.get_or_init(tokio::time::Instant::now).elapsed()
+    }
+}
+
+/// Delegates to tokio, counting the sleeps started through it
+pub struct CountingTimer;
+
+impl tokio_timeout::Timer for CountingTimer {
+    type Sleep = tokio::time::Sleep;
+
+    fn sleep(duration: Duration) -> Self::Sleep {
+        SLEEPS.fetch_add(1, Ordering::SeqCst);
+        TokioTimer::sleep(duration)
+    }
+
+    fn now() -> Duration {
+        TokioTimer::now()
+    }
+}
+
+/// A clock that never moves, so that nothing ever times out
+pub struct FrozenTimer;
+
+impl tokio_timeout::Timer for FrozenTimer {
+    type Sleep = std::future::Pending<()>;
+
+    fn sleep(_duration: Duration) -> Self::Sleep {
+        std::future::pending()
+    }
+
+    fn now() -> Duration {
+        Duration::ZERO
+    }
+}
+
+mod timers {
+    pub use super::TokioTimer;
+}
+
+#[timeout(duration = "1s", on_error = "panic", backend = TokioTimer)]
+pub async fn my_fast_fn() -> u32 {
+    1
+}
+
+#[timeout(duration = "1ms", on_error = "into", backend = crate::timers::TokioTimer)]
+pub async fn my_into_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(duration = "1ms", on_error = "panic", backend = TokioTimer)]
+pub async fn my_panicking_fn() {
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+}
+
+fn ctx_err(
+    ctx: tokio_timeout::TimeoutContext<tokio_timeout::Elapsed>,
+) -> Result<(), tokio_timeout::TimeoutContext<tokio_timeout::Elapsed>> {
+    Err(ctx)
+}
+
+#[timeout(duration = "1ms", on_error_ctx = ctx_err, backend = TokioTimer)]
+pub async fn my_ctx_fn() -> Result<(), tokio_timeout::TimeoutContext<tokio_timeout::Elapsed>> {
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(
+    duration = "1ms",
+    on_error = |msg: &'static str| Err(msg),
+    retries = 2,
+    backoff = "1ms",
+    total = "1s",
+    backend = CountingTimer
+)]
+pub async fn my_retried_fn() -> Result<(), &'static str> {
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(duration = "1ms", on_error = "panic", backend = FrozenTimer)]
+pub async fn my_frozen_fn() -> u32 {
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    2
+}
+
+#[tokio::test]
+async fn times_out_through_backend() {
+    assert_eq!(1, my_fast_fn().await);
+    let err = my_into_fn().await.unwrap_err();
+    assert_eq!("my_into_fn", err.fn_name());
+    assert_eq!(Duration::from_millis(1), err.duration());
+    let ctx = my_ctx_fn().await.unwrap_err();
+    assert_eq!("my_ctx_fn", ctx.fn_name);
+    assert!(ctx.elapsed >= Duration::from_millis(1));
+    assert_eq!("deadline has elapsed", ctx.error.to_string());
+    let msg = my_retried_fn().await.unwrap_err();
+    assert!(msg.contains("3 of 3 attempts timed out"), "{msg}");
+    // A timer per attempt and a backoff between them
+    assert_eq!(5, SLEEPS.load(Ordering::SeqCst));
+    assert_eq!(2, my_frozen_fn().await);
+}
+
+#[tokio::test]
+#[should_panic(expected = "'my_panicking_fn' timed out after 1ms")]
+async fn panics_through_backend() {
+    my_panicking_fn().await;
+}
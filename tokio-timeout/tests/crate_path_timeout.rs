@@ -0,0 +1,53 @@
+//! tokio found through the `crate` attribute, and unaffected by a local `mod tokio`
+//! with the default absolute path.
+use std::time::Duration;
+use tokio_timeout::timeout;
+
+/// A facade re-exporting tokio, like a platform crate would
+pub mod our_rt {
+    pub use tokio;
+}
+
+/// Shadows tokio for relative paths
+mod tokio {}
+
+#[timeout(duration = "1s", on_error = "panic", crate = crate::our_rt::tokio)]
+pub async fn my_facade_fn() -> u32 {
+    1
+}
+
+#[timeout(duration = "1ms", on_error = "into", crate = our_rt::tokio)]
+pub async fn my_facade_into_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    ::tokio::time::sleep(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(
+    duration = "5ms",
+    on_error = "panic",
+    retries = 1,
+    backoff = "1ms",
+    total = "1s",
+    crate = crate::our_rt::tokio
+)]
+pub async fn my_facade_retried_fn() -> u32 {
+    2
+}
+
+#[timeout(duration = "1s", on_error = "panic")]
+pub async fn my_shadowed_fn() -> u32 {
+    3
+}
+
+#[::tokio::test(crate = "::tokio")]
+async fn finds_tokio_through_facade() {
+    assert_eq!(1, my_facade_fn().await);
+    let err = my_facade_into_fn().await.unwrap_err();
+    assert_eq!("my_facade_into_fn", err.fn_name());
+    assert_eq!(2, my_facade_retried_fn().await);
+}
+
+#[::tokio::test(crate = "::tokio")]
+async fn ignores_local_tokio_module() {
+    assert_eq!(3, my_shadowed_fn().await);
+}
@@ -0,0 +1,186 @@
+//! The `runtime = "embassy"` expansion, run against a stand-in for the parts of embassy-time
+//! that it uses, built on tokio's timer the way embassy-time's std driver is built on threads.
+//!
+//! The real embassy-time, with its `std` and `generic-queue` features, can't be a dev-dependency:
+//! its 0.3, 0.4 and 0.5 releases depend on embedded-hal-async 1.0, which needs rustc 1.75,
+//! above this workspace's MSRV of 1.70. `test-crates/embassy` runs these tests against it on
+//! stable instead.
+extern crate self as embassy_time;
+
+use std::cell::Cell;
+use std::sync::atomic::Ordering;
+use tokio_timeout::timeout;
+
+thread_local! {
+    /// The microseconds given to the last `with_timeout` on this test's thread
+    static LAST_TIMEOUT: Cell<u64> = Cell::new(0);
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Duration {
+    micros: u64,
+}
+
+impl Duration {
+    pub const fn from_micros(micros: u64) -> Self {
+        Duration { micros }
+    }
+
+    pub const fn as_micros(&self) -> u64 {
+        self.micros
+    }
+
+    fn to_std(self) -> std::time::Duration {
+        std::time::Duration::from_micros(self.micros)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Instant(tokio::time::Instant);
+
+impl Instant {
+    pub fn now() -> Self {
+        Instant(tokio::time::Instant::now())
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_micros(u64::try_from(self.0.elapsed().as_micros()).unwrap())
+    }
+}
+
+pub struct Timer;
+
+impl Timer {
+    pub fn after(duration: Duration) -> tokio::time::Sleep {
+        tokio::time::sleep(duration.to_std())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimeoutError;
+
+pub async fn with_timeout<F: std::future::Future>(
+    timeout: Duration,
+    fut: F,
+) -> Result<F::Output, TimeoutError> {
+    LAST_TIMEOUT.with(|last| last.set(timeout.as_micros()));
+    tokio::time::timeout(timeout.to_std(), fut)
+        .await
+        .map_err(|_| TimeoutError)
+}
+
+#[timeout(duration = "1s", on_error = "panic", runtime = "embassy")]
+pub async fn my_fast_fn() -> u32 {
+    1
+}
+
+#[timeout(duration = "1ms", on_error = "panic", runtime = "embassy")]
+pub async fn my_panicking_fn() {
+    Timer::after(Duration::from_micros(1_000_000)).await;
+}
+
+#[timeout(duration = "1ms", on_error = "into", runtime = "embassy")]
+pub async fn my_into_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    Timer::after(Duration::from_micros(1_000_000)).await;
+    Ok(())
+}
+
+fn ctx_err(
+    ctx: tokio_timeout::TimeoutContext<embassy_time::TimeoutError>,
+) -> Result<(), tokio_timeout::TimeoutContext<embassy_time::TimeoutError>> {
+    Err(ctx)
+}
+
+#[timeout(duration = "1ms", on_error_ctx = ctx_err, runtime = "embassy")]
+pub async fn my_ctx_fn() -> Result<(), tokio_timeout::TimeoutContext<embassy_time::TimeoutError>> {
+    Timer::after(Duration::from_micros(1_000_000)).await;
+    Ok(())
+}
+
+#[timeout(duration = "1500ns", on_error = "panic", runtime = "embassy")]
+pub async fn my_sub_micro_fn() -> u32 {
+    2
+}
+
+const ATTEMPT: std::time::Duration = std::time::Duration::from_nanos(5_000_500);
+
+#[timeout(duration = ATTEMPT, on_error = "panic", runtime = "embassy")]
+pub async fn my_const_fn() -> u32 {
+    3
+}
+
+static FLAKY_CALLS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+#[timeout(
+    duration = "5ms",
+    on_error = "panic",
+    retries = 2,
+    backoff = "1ms",
+    total = "1s",
+    runtime = "embassy"
+)]
+pub async fn my_flaky_fn() -> u32 {
+    let call = FLAKY_CALLS.fetch_add(1, Ordering::SeqCst) + 1;
+    if call < 3 {
+        Timer::after(Duration::from_micros(1_000_000)).await;
+    }
+    call
+}
+
+static SLOW_WARNINGS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+fn count_slow(_msg: &'static str) {
+    SLOW_WARNINGS.fetch_add(1, Ordering::SeqCst);
+}
+
+#[timeout(duration = "1s", on_error = "panic", warn_after = "1ms", on_slow = count_slow, runtime = "embassy")]
+pub async fn my_slow_fn() -> u32 {
+    Timer::after(Duration::from_micros(10_000)).await;
+    4
+}
+
+#[timeout(duration = "none", on_error = "panic", runtime = "embassy")]
+pub async fn my_unbounded_fn() -> u32 {
+    Timer::after(Duration::from_micros(10_000)).await;
+    5
+}
+
+#[tokio::test]
+async fn completes_in_time() {
+    assert_eq!(1, my_fast_fn().await);
+    assert_eq!(5, my_unbounded_fn().await);
+}
+
+#[tokio::test]
+#[should_panic(expected = "'my_panicking_fn' timed out after 1ms")]
+async fn panics_on_timeout() {
+    my_panicking_fn().await;
+}
+
+#[tokio::test]
+async fn errors_on_timeout() {
+    let err = my_into_fn().await.unwrap_err();
+    assert_eq!("my_into_fn", err.fn_name());
+    assert_eq!(std::time::Duration::from_millis(1), err.duration());
+    let ctx = my_ctx_fn().await.unwrap_err();
+    assert_eq!("my_ctx_fn", ctx.fn_name);
+    assert_eq!(embassy_time::TimeoutError, ctx.error);
+    assert!(ctx.elapsed >= std::time::Duration::from_millis(1));
+}
+
+#[tokio::test]
+async fn rounds_up_to_whole_micros() {
+    // Converted at expansion time
+    assert_eq!(2, my_sub_micro_fn().await);
+    assert_eq!(2, LAST_TIMEOUT.with(Cell::get));
+    // Converted at runtime
+    assert_eq!(3, my_const_fn().await);
+    assert_eq!(5001, LAST_TIMEOUT.with(Cell::get));
+}
+
+#[tokio::test]
+async fn retries_and_warns() {
+    assert_eq!(3, my_flaky_fn().await);
+    assert_eq!(4, my_slow_fn().await);
+    assert_eq!(1, SLOW_WARNINGS.load(Ordering::SeqCst));
+}
@@ -0,0 +1,121 @@
+//! The expansion can't be broken by names in the user's scope, each module shadows
+//! something the expanded code relies on.
+use std::time::Duration;
+
+mod ok_type {
+    use tokio_timeout::timeout;
+
+    /// A user type named `Ok`, which a bare `Ok(v)` pattern would match against
+    #[allow(dead_code)]
+    pub struct Ok(pub u32);
+
+    #[timeout(duration = "1s", on_error = "panic")]
+    pub async fn my_fast_fn() -> u32 {
+        1
+    }
+
+    #[timeout(duration = "1ms", on_error = "default")]
+    pub async fn my_slow_fn() -> u32 {
+        tokio::time::sleep(super::Duration::from_millis(1000)).await;
+        2
+    }
+}
+
+mod panic_override {
+    use tokio_timeout::timeout;
+
+    /// Swallows the panic, a bare `panic!` would silently stop panicking on timeout
+    #[allow(unused_macros)]
+    macro_rules! panic {
+        ($($t:tt)*) => {
+            ()
+        };
+    }
+
+    #[timeout(duration = "1ms", on_error = "panic")]
+    pub async fn my_panicking_fn() {
+        tokio::time::sleep(super::Duration::from_millis(1000)).await;
+    }
+
+    #[timeout(duration = "1ms", jitter = "10%", on_error = "panic")]
+    pub async fn my_jittered_panicking_fn() {
+        tokio::time::sleep(super::Duration::from_millis(1000)).await;
+    }
+}
+
+mod result_glob {
+    use tokio_timeout::timeout;
+
+    #[allow(dead_code)]
+    pub enum MyResult<T, E> {
+        Ok(T),
+        Err(E),
+    }
+
+    #[allow(unused_imports)]
+    use MyResult::*;
+
+    #[timeout(duration = "1s", on_error = "panic")]
+    pub async fn my_fast_fn() -> u32 {
+        3
+    }
+
+    #[timeout(duration = "1ms", on_error = "into")]
+    pub async fn my_into_fn() -> std::result::Result<(), tokio_timeout::TimeoutError> {
+        tokio::time::sleep(super::Duration::from_millis(1000)).await;
+        std::result::Result::Ok(())
+    }
+
+    #[timeout(
+        duration = "1ms",
+        on_error = "panic",
+        retries = 1,
+        backoff = "1ms",
+        total = "1s"
+    )]
+    pub async fn my_retried_fn() -> u32 {
+        4
+    }
+}
+
+mod bindings {
+    use tokio_timeout::timeout;
+
+    /// `e` and `v` are the function's own arguments, not the expansion's match bindings
+    #[timeout(duration = "1ms", fallback = e + v)]
+    pub async fn my_fallback_fn(e: u32, v: u32) -> u32 {
+        tokio::time::sleep(super::Duration::from_millis(1000)).await;
+        e * v
+    }
+}
+
+#[tokio::test]
+async fn user_ok_type() {
+    assert_eq!(1, ok_type::my_fast_fn().await);
+    assert_eq!(0, ok_type::my_slow_fn().await);
+}
+
+#[tokio::test]
+#[should_panic(expected = "'my_panicking_fn' timed out after 1ms")]
+async fn panic_macro_override() {
+    panic_override::my_panicking_fn().await;
+}
+
+#[tokio::test]
+#[should_panic(expected = "'my_jittered_panicking_fn' timed out after 1ms")]
+async fn panic_macro_override_with_jitter() {
+    panic_override::my_jittered_panicking_fn().await;
+}
+
+#[tokio::test]
+async fn result_variants_glob_imported() {
+    assert_eq!(3, result_glob::my_fast_fn().await);
+    let err = result_glob::my_into_fn().await.unwrap_err();
+    assert_eq!("my_into_fn", err.fn_name());
+    assert_eq!(4, result_glob::my_retried_fn().await);
+}
+
+#[tokio::test]
+async fn user_bindings_named_like_internal_ones() {
+    assert_eq!(5, bindings::my_fallback_fn(2, 3).await);
+}
@@ -0,0 +1,51 @@
+//! The runtime duration API, parsing with the attribute's grammar through `tokio-timeout` alone.
+use std::time::Duration;
+use tokio_timeout::{
+    format_duration, parse_duration, parse_timeout, timeout, ParseDurationErrorKind,
+};
+
+fn configured(value: &str) -> anyhow::Result<Duration> {
+    Ok(parse_duration(value)?)
+}
+
+#[timeout(duration = "1.5ms", on_error = "into")]
+pub async fn my_literal_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[test]
+fn errors_convert_into_other_error_types() {
+    assert_eq!(Duration::from_secs(90), configured("1m30s").unwrap());
+    let err = configured("1m30").unwrap_err();
+    let parse_err = err
+        .downcast_ref::<tokio_timeout::ParseDurationError>()
+        .unwrap();
+    assert_eq!(ParseDurationErrorKind::MissingUnit, parse_err.kind());
+    assert_eq!(parse_err.to_string(), err.to_string());
+}
+
+#[test]
+fn formatted_durations_parse_back() {
+    for dur in [
+        Duration::from_nanos(1),
+        Duration::from_micros(250),
+        Duration::from_millis(1500),
+        Duration::from_secs(3720),
+        Duration::from_secs(90_061),
+    ] {
+        let formatted = format_duration(dur).to_string();
+        assert_eq!(dur, parse_duration(&formatted).unwrap(), "{formatted}");
+    }
+    assert_eq!(None, parse_timeout(" none ").unwrap());
+    assert_eq!(
+        ParseDurationErrorKind::Zero,
+        parse_timeout("0s").unwrap_err().kind()
+    );
+}
+
+#[tokio::test]
+async fn matches_the_attribute_grammar() {
+    let err = my_literal_fn().await.unwrap_err();
+    assert_eq!(parse_duration("1.5ms").unwrap(), err.duration());
+}
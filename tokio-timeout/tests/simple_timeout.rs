@@ -49,3 +49,608 @@ async fn smoke_times_out() {
     let err = my_will_time_out_fn().await.err().unwrap();
     assert!(matches!(err, MyErr::Timeout(_)));
 }
+
+#[timeout(duration = "1ms", on_error = "default")]
+pub async fn my_will_time_out_default() -> Vec<u8> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    vec![1, 2, 3]
+}
+
+static LOGGED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+fn log_timeout(_msg: &'static str) {
+    LOGGED.store(true, std::sync::atomic::Ordering::SeqCst);
+}
+
+#[timeout(duration = "1ms", on_error = "default", log = log_timeout)]
+pub async fn my_will_time_out_default_logged() -> Option<u8> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Some(1)
+}
+
+#[tokio::test]
+async fn default_on_timeout() {
+    assert!(my_will_time_out_default().await.is_empty());
+    assert!(my_will_time_out_default_logged().await.is_none());
+    assert!(LOGGED.load(std::sync::atomic::Ordering::SeqCst));
+}
+
+pub enum MyOwnedErr {
+    Timeout(String),
+}
+
+fn prefixed_handler(prefix: &'static str) -> impl Fn(&'static str) -> Result<String, MyOwnedErr> {
+    move |msg| Err(MyOwnedErr::Timeout(format!("{prefix}{msg}")))
+}
+
+#[timeout(duration = "1ms", on_error = |msg: &str| Err(MyOwnedErr::Timeout(msg.into())))]
+pub async fn my_will_time_out_closure() -> Result<String, MyOwnedErr> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(String::new())
+}
+
+#[timeout(duration = "1ms", on_error = prefixed_handler("prefix: "))]
+pub async fn my_will_time_out_expr() -> Result<String, MyOwnedErr> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(String::new())
+}
+
+// The commas in the return type and closure parameters aren't inside a group
+#[timeout(
+    duration = "1ms",
+    on_error = |msg: &'static str| -> Result<u32, String> { Err(format!("typed: {msg}")) },
+    log = |msg, | log_timeout(msg)
+)]
+pub async fn my_will_time_out_typed_closure() -> Result<u32, String> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(0)
+}
+
+#[tokio::test]
+async fn expression_on_error() {
+    let err = my_will_time_out_closure().await.err().unwrap();
+    assert!(matches!(err, MyOwnedErr::Timeout(ref s) if s.contains("my_will_time_out_closure")));
+    let err = my_will_time_out_expr().await.err().unwrap();
+    assert!(matches!(err, MyOwnedErr::Timeout(ref s) if s.starts_with("prefix: ")));
+    let err = my_will_time_out_typed_closure().await.unwrap_err();
+    assert!(err.starts_with("typed: "), "{err}");
+}
+
+#[timeout(duration = "1ms", on_error = "into")]
+pub async fn my_will_time_out_io() -> std::io::Result<()> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(duration = crate::MY_DUR, on_error = "into")]
+pub async fn my_will_time_out_anyhow() -> anyhow::Result<()> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn into_on_timeout() {
+    let err = my_will_time_out_io().await.unwrap_err();
+    assert_eq!(std::io::ErrorKind::TimedOut, err.kind());
+    let err = my_will_time_out_anyhow().await.unwrap_err();
+    let err = err.downcast::<tokio_timeout::TimeoutError>().unwrap();
+    assert_eq!("my_will_time_out_anyhow", err.fn_name());
+    assert_eq!(MY_DUR, err.duration());
+}
+
+fn ctx_err(
+    ctx: tokio_timeout::TimeoutContext<tokio::time::error::Elapsed>,
+) -> Result<(), tokio_timeout::TimeoutContext<tokio::time::error::Elapsed>> {
+    Err(ctx)
+}
+
+#[timeout(duration = crate::MY_DUR, on_error_ctx = ctx_err)]
+pub async fn my_will_time_out_ctx(
+) -> Result<(), tokio_timeout::TimeoutContext<tokio::time::error::Elapsed>> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn ctx_on_timeout() {
+    let ctx = my_will_time_out_ctx().await.unwrap_err();
+    assert_eq!("my_will_time_out_ctx", ctx.fn_name);
+    assert_eq!(module_path!(), ctx.module_path);
+    assert_eq!(file!(), ctx.file);
+    assert_eq!(MY_DUR, ctx.duration);
+    assert!(ctx.elapsed >= MY_DUR);
+}
+
+pub struct CachedClient {
+    cached: Vec<u8>,
+}
+
+impl CachedClient {
+    #[timeout(duration = "1ms", fallback = self.cached.clone())]
+    pub async fn will_time_out_fetch(&self) -> Vec<u8> {
+        tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+        vec![1, 2, 3]
+    }
+}
+
+#[tokio::test]
+async fn fallback_on_timeout() {
+    let client = CachedClient { cached: vec![4] };
+    assert_eq!(vec![4], client.will_time_out_fetch().await);
+}
+
+static FLAKY_CALLS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+#[timeout(duration = "5ms", on_error = my_err, retries = 2, backoff = "1ms", backoff_strategy = "exponential")]
+pub async fn my_flaky_fn(hang_until: u32) -> Result<String, MyErr> {
+    if FLAKY_CALLS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1 < hang_until {
+        tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    }
+    Ok(String::new())
+}
+
+fn my_owned_err(msg: &'static str) -> Result<(), String> {
+    Err(msg.to_string())
+}
+
+#[timeout(duration = "1ms", on_error = my_owned_err, retries = 2)]
+pub async fn my_will_exhaust_retries() -> Result<(), String> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(duration = "10ms", on_error = my_owned_err, retries = 10, backoff = "5ms", total = "30ms")]
+pub async fn my_will_exhaust_budget() -> Result<(), String> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+static CONSUMING_CALLS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+// Each attempt consumes its own clone of `payload`, `tag` is only borrowed
+#[timeout(duration = "5ms", on_error = "panic", retries = 2)]
+pub async fn my_consuming_flaky_fn(payload: String, tag: &str) -> Vec<u8> {
+    if CONSUMING_CALLS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 2 {
+        tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    }
+    let mut bytes = payload.into_bytes();
+    bytes.extend(tag.as_bytes());
+    bytes
+}
+
+#[tokio::test]
+async fn retries_on_timeout() {
+    assert!(my_flaky_fn(3).await.is_ok());
+    assert_eq!(3, FLAKY_CALLS.load(std::sync::atomic::Ordering::SeqCst));
+    let msg = my_will_exhaust_retries().await.unwrap_err();
+    assert!(msg.contains("3 of 3 attempts timed out"), "{msg}");
+    let msg = my_will_exhaust_budget().await.unwrap_err();
+    assert!(msg.contains("total budget of"), "{msg}");
+    assert_eq!(
+        b"payload!".to_vec(),
+        my_consuming_flaky_fn("payload".to_string(), "!").await
+    );
+    assert_eq!(3, CONSUMING_CALLS.load(std::sync::atomic::Ordering::SeqCst));
+}
+
+static SLOW_WARNINGS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+fn count_slow(_msg: &'static str) {
+    SLOW_WARNINGS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+}
+
+#[timeout(duration = "1s", on_error = "panic", warn_after = "1ms", on_slow = count_slow)]
+pub async fn my_slow_fn(sleep_ms: u64) -> u64 {
+    tokio::time::sleep(core::time::Duration::from_millis(sleep_ms)).await;
+    sleep_ms
+}
+
+#[timeout(duration = "1s", on_error = "panic", warn_after = "500ms")]
+pub async fn my_default_slow_warning_fn() {}
+
+#[tokio::test]
+async fn warns_when_slow() {
+    assert_eq!(20, my_slow_fn(20).await);
+    assert_eq!(1, SLOW_WARNINGS.load(std::sync::atomic::Ordering::SeqCst));
+    my_default_slow_warning_fn().await;
+}
+
+pub struct Config {
+    timeout_ms: u64,
+}
+
+impl Config {
+    fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    fn get<Lo: Default + Into<u64>, Hi: Default + Into<u64>>(&self) -> Duration {
+        self.timeout() + Duration::from_millis(Lo::default().into() + Hi::default().into())
+    }
+}
+
+pub struct ConfiguredClient {
+    config: Config,
+}
+
+impl ConfiguredClient {
+    #[timeout(duration = self.config.timeout(), on_error = my_err)]
+    pub async fn will_time_out_configured(&self) -> Result<String, MyErr> {
+        tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+        Ok(String::new())
+    }
+
+    // The comma in the turbofish isn't inside a group
+    #[timeout(duration = self.config.get::<u8, u16>(), strict, on_error = my_err)]
+    pub async fn will_time_out_generic(&self) -> Result<String, MyErr> {
+        tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+        Ok(String::new())
+    }
+}
+
+#[timeout(duration = Duration::from_millis(timeout_ms), on_error = my_err)]
+pub async fn my_arg_timeout_fn(timeout_ms: u64, sleep_ms: u64) -> Result<String, MyErr> {
+    tokio::time::sleep(core::time::Duration::from_millis(sleep_ms)).await;
+    Ok(String::new())
+}
+
+#[tokio::test]
+async fn expression_durations() {
+    let client = ConfiguredClient {
+        config: Config { timeout_ms: 1 },
+    };
+    assert!(client.will_time_out_configured().await.is_err());
+    assert!(client.will_time_out_generic().await.is_err());
+    assert!(my_arg_timeout_fn(1, 1000).await.is_err());
+    assert!(my_arg_timeout_fn(1000, 1).await.is_ok());
+}
+
+#[timeout(duration_env = "TOKIO_TIMEOUT_TEST_ENV_DURATION", duration = "1h", on_error = my_err)]
+pub async fn my_env_timeout_fn() -> Result<String, MyErr> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(String::new())
+}
+
+#[timeout(duration_env = "TOKIO_TIMEOUT_TEST_ENV_DURATION_INVALID", duration = "1ms", on_error = my_err)]
+pub async fn my_invalid_env_timeout_fn() -> Result<String, MyErr> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(String::new())
+}
+
+#[tokio::test]
+async fn env_durations() {
+    std::env::set_var("TOKIO_TIMEOUT_TEST_ENV_DURATION", "1ms");
+    std::env::set_var("TOKIO_TIMEOUT_TEST_ENV_DURATION_INVALID", "1z");
+    assert!(my_env_timeout_fn().await.is_err());
+    assert!(my_invalid_env_timeout_fn().await.is_err());
+}
+
+#[timeout(duration = "1.5ms", on_error = "into")]
+pub async fn my_fractional_timeout_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn fractional_durations() {
+    let err = my_fractional_timeout_fn().await.unwrap_err();
+    assert_eq!(Duration::from_micros(1500), err.duration());
+}
+
+#[timeout(duration = "500µs", on_error = "into")]
+pub async fn my_micros_timeout_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(duration = "1w1d", on_error = "into")]
+pub async fn my_days_timeout_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    Ok(())
+}
+
+#[tokio::test]
+async fn small_and_large_units() {
+    let err = my_micros_timeout_fn().await.unwrap_err();
+    assert_eq!(Duration::from_micros(500), err.duration());
+    assert!(my_days_timeout_fn().await.is_ok());
+}
+
+#[timeout(duration = "2 millis 500 usec", on_error = "into")]
+pub async fn my_long_form_timeout_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn long_form_durations() {
+    let err = my_long_form_timeout_fn().await.unwrap_err();
+    assert_eq!(Duration::from_micros(2500), err.duration());
+}
+
+#[timeout(duration = "PT0.0025S", on_error = "into")]
+pub async fn my_iso_timeout_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn iso8601_durations() {
+    let err = my_iso_timeout_fn().await.unwrap_err();
+    assert_eq!(Duration::from_micros(2500), err.duration());
+}
+
+#[timeout(
+    strict,
+    duration = "1ms 500us",
+    on_error = "into",
+    retries = 1,
+    backoff = "1ms"
+)]
+pub async fn my_strict_timeout_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn strict_durations() {
+    let err = my_strict_timeout_fn().await.unwrap_err();
+    assert_eq!(Duration::from_micros(1500), err.duration());
+}
+
+#[timeout(duration = "1.5ms", on_error = |msg: &'static str| Err(msg))]
+pub async fn my_default_message_fn() -> Result<(), &'static str> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(
+    duration = "1.5ms",
+    on_error = |msg: &'static str| Err(msg),
+    message = "upstream {fn} in {module} exceeded {duration} at {location} {{literal}}"
+)]
+pub async fn my_templated_message_fn() -> Result<(), &'static str> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(
+    duration = "1ms",
+    on_error = |msg: &'static str| Err(msg),
+    message = "{fn} exceeded {duration}",
+    retries = 1
+)]
+pub async fn my_templated_retry_fn() -> Result<(), &'static str> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn message_templates() {
+    assert_eq!(
+        "'my_default_message_fn' timed out after 1.5ms",
+        my_default_message_fn().await.unwrap_err()
+    );
+    let msg = my_templated_message_fn().await.unwrap_err();
+    let expect = format!(
+        "upstream my_templated_message_fn in {} exceeded 1.5ms at {}:",
+        module_path!(),
+        file!()
+    );
+    assert!(msg.starts_with(&expect), "{msg}");
+    assert!(msg.ends_with(" {literal}"), "{msg}");
+    assert_eq!(
+        "my_templated_retry_fn exceeded 1ms, 2 of 2 attempts timed out",
+        my_templated_retry_fn().await.unwrap_err()
+    );
+}
+
+#[timeout(duration = "20ms", jitter = "10ms", on_error = "into")]
+pub async fn my_jittered_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(duration = "20ms", jitter = "50%", on_error = "into")]
+pub async fn my_percent_jittered_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+static JITTER_LOGS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+fn log_jitter(msg: &str) {
+    JITTER_LOGS.lock().unwrap().push(msg.to_string());
+}
+
+#[timeout(
+    duration = "1ms",
+    jitter = "10%",
+    on_error = |msg: &str| Err(msg.to_string()),
+    log = log_jitter
+)]
+pub async fn my_jittered_message_fn() -> Result<(), String> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(duration = "1ms", jitter = "500us", on_error = "panic")]
+pub async fn my_jittered_panic_fn() {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+}
+
+#[tokio::test]
+async fn jittered_timeouts() {
+    let mut fixed = Vec::new();
+    let mut percent = Vec::new();
+    for _ in 0..10 {
+        fixed.push(my_jittered_fn().await.unwrap_err().duration());
+        percent.push(my_percent_jittered_fn().await.unwrap_err().duration());
+    }
+    for dur in fixed.iter().chain(&percent) {
+        assert!(
+            (Duration::from_millis(10)..=Duration::from_millis(30)).contains(dur),
+            "{dur:?}"
+        );
+    }
+    assert!(fixed.iter().any(|d| *d != fixed[0]), "{fixed:?}");
+    assert!(percent.iter().any(|d| *d != percent[0]), "{percent:?}");
+    let msg = my_jittered_message_fn().await.unwrap_err();
+    let effective = msg
+        .strip_prefix("'my_jittered_message_fn' timed out after 1ms \u{b1}10%, effective timeout ")
+        .unwrap_or_else(|| panic!("{msg}"));
+    assert!(effective.ends_with('s'), "{msg}");
+    assert_eq!(vec![msg], *JITTER_LOGS.lock().unwrap());
+}
+
+#[timeout(
+    duration = Duration::from_millis(timeout_ms),
+    on_error = "into",
+    jitter = "50%",
+    min = "15ms",
+    max = "25ms"
+)]
+pub async fn my_jittered_clamped_fn(timeout_ms: u64) -> Result<(), tokio_timeout::TimeoutError> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn jittered_timeouts_stay_within_bounds() {
+    let bounds = Duration::from_millis(15)..=Duration::from_millis(25);
+    for _ in 0..10 {
+        // Jittered within 10ms..=30ms, then clamped
+        let dur = my_jittered_clamped_fn(20).await.unwrap_err().duration();
+        assert!(bounds.contains(&dur), "{dur:?}");
+    }
+    assert_eq!(
+        Duration::from_millis(15),
+        my_jittered_clamped_fn(0).await.unwrap_err().duration()
+    );
+    assert_eq!(
+        Duration::from_millis(25),
+        my_jittered_clamped_fn(u64::MAX)
+            .await
+            .unwrap_err()
+            .duration()
+    );
+}
+
+#[tokio::test]
+#[should_panic(expected = "timed out after 1ms \u{b1}500us, effective timeout")]
+async fn jittered_panic_reports_effective_timeout() {
+    my_jittered_panic_fn().await;
+}
+
+#[timeout(duration = Duration::from_millis(timeout_ms), on_error = "panic", min = "2ms")]
+pub async fn my_clamped_panic_fn(timeout_ms: u64) {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+}
+
+#[timeout(
+    duration = Duration::from_millis(timeout_ms),
+    on_error = |msg: &str| Err(msg.to_string()),
+    max = "2ms"
+)]
+pub async fn my_clamped_message_fn(timeout_ms: u64) -> Result<(), String> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn clamped_message_reports_effective_timeout() {
+    let msg = my_clamped_message_fn(u64::MAX).await.unwrap_err();
+    assert!(msg.ends_with(", effective timeout 2ms"), "{msg}");
+}
+
+#[tokio::test]
+#[should_panic(expected = ", effective timeout 2ms")]
+async fn clamped_panic_reports_effective_timeout() {
+    my_clamped_panic_fn(0).await;
+}
+
+static CLAMPS: std::sync::Mutex<Vec<tokio_timeout::DurationClamp>> =
+    std::sync::Mutex::new(Vec::new());
+
+fn record_clamp(clamp: tokio_timeout::DurationClamp) {
+    CLAMPS.lock().unwrap().push(clamp);
+}
+
+#[timeout(
+    duration = Duration::from_millis(timeout_ms),
+    on_error = "into",
+    min = "2ms",
+    max = "5ms",
+    on_clamp = record_clamp
+)]
+pub async fn my_clamped_fn(timeout_ms: u64) -> Result<(), tokio_timeout::TimeoutError> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(duration = Duration::from_millis(timeout_ms), on_error = "into", min = "2ms")]
+pub async fn my_min_clamped_fn(timeout_ms: u64) -> Result<(), tokio_timeout::TimeoutError> {
+    tokio::time::sleep(core::time::Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn clamped_durations() {
+    let clamped = |ms| async move { my_clamped_fn(ms).await.unwrap_err().duration() };
+    assert_eq!(Duration::from_millis(2), clamped(0).await);
+    assert_eq!(Duration::from_millis(3), clamped(3).await);
+    assert_eq!(Duration::from_millis(5), clamped(u64::MAX).await);
+    let clamps = CLAMPS.lock().unwrap().clone();
+    assert_eq!(2, clamps.len());
+    assert_eq!("my_clamped_fn", clamps[0].fn_name);
+    assert_eq!(Duration::ZERO, clamps[0].configured);
+    assert_eq!(Duration::from_millis(2), clamps[0].clamped);
+    assert_eq!(Duration::from_millis(u64::MAX), clamps[1].configured);
+    assert_eq!(Duration::from_millis(5), clamps[1].clamped);
+    assert_eq!(
+        Duration::from_millis(2),
+        my_min_clamped_fn(0).await.unwrap_err().duration()
+    );
+}
+
+#[timeout(duration = "none", on_error = "panic")]
+pub async fn my_unbounded_fn() -> u32 {
+    tokio::time::sleep(core::time::Duration::from_millis(20)).await;
+    1
+}
+
+#[timeout(duration = "infinite", on_error = "panic")]
+pub async fn my_infinite_fn() -> u32 {
+    2
+}
+
+#[timeout(duration = timeout, on_error = "into")]
+pub async fn my_optional_fn(timeout: Option<Duration>) -> Result<u32, tokio_timeout::TimeoutError> {
+    tokio::time::sleep(core::time::Duration::from_millis(20)).await;
+    Ok(3)
+}
+
+#[timeout(
+    duration_env = "TOKIO_TIMEOUT_TEST_ENV_DURATION_NONE",
+    duration = "1ms",
+    on_error = "panic"
+)]
+pub async fn my_env_unbounded_fn() -> u32 {
+    tokio::time::sleep(core::time::Duration::from_millis(20)).await;
+    4
+}
+
+#[tokio::test]
+async fn unbounded_durations() {
+    assert_eq!(1, my_unbounded_fn().await);
+    assert_eq!(2, my_infinite_fn().await);
+    assert_eq!(Ok(3), my_optional_fn(None).await);
+    assert_eq!(
+        Duration::from_millis(1),
+        my_optional_fn(Some(Duration::from_millis(1)))
+            .await
+            .unwrap_err()
+            .duration()
+    );
+    std::env::set_var("TOKIO_TIMEOUT_TEST_ENV_DURATION_NONE", "none");
+    assert_eq!(4, my_env_unbounded_fn().await);
+}
@@ -0,0 +1,564 @@
+//! `simple_timeout.rs` mirrored for `runtime = "smol"`, run against the real async-io timer
+//! that the expansion uses, on async-io's own executor.
+use std::time::Duration;
+use tokio_timeout::timeout;
+
+#[timeout(duration = "1m10s", on_error = "panic", runtime = "smol")]
+pub async fn my_panic_fn() {}
+
+pub enum MyErr {
+    Timeout(&'static str),
+}
+
+fn my_err(msg: &'static str) -> Result<String, MyErr> {
+    Err(MyErr::Timeout(msg))
+}
+
+#[timeout(duration = "5ms", on_error = my_err, runtime = "smol")]
+pub async fn my_res_fn() -> Result<String, MyErr> {
+    Ok(String::new())
+}
+
+#[timeout(duration = "1ms", on_error = my_err, runtime = "smol")]
+pub async fn my_will_time_out_fn() -> Result<String, MyErr> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(String::new())
+}
+
+const MY_DUR: Duration = Duration::from_millis(1);
+
+#[timeout(duration = crate::MY_DUR, on_error = my_err, runtime = "smol")]
+pub async fn my_will_time_out_const() -> Result<String, MyErr> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(String::new())
+}
+
+pub struct StructWithTimeoutImpl;
+
+impl StructWithTimeoutImpl {
+    #[timeout(duration = "1ms", on_error = "panic", runtime = "smol")]
+    pub async fn static_timeout_method() {}
+
+    #[timeout(duration = "5ms", on_error = "panic", runtime = "smol")]
+    pub async fn with_self_timeout_method(&self) {}
+
+    #[timeout(duration = "5ms", on_error = "panic", runtime = "smol")]
+    pub async fn with_generics_timeout_method<T, R>(&self, _t: T, _r: R) {}
+}
+
+#[test]
+fn smoke_completes() {
+    async_io::block_on(async {
+        my_panic_fn().await;
+        assert!(my_res_fn().await.is_ok());
+        StructWithTimeoutImpl::static_timeout_method().await;
+        StructWithTimeoutImpl.with_self_timeout_method().await;
+        StructWithTimeoutImpl
+            .with_generics_timeout_method(1, "r")
+            .await;
+    });
+}
+
+#[test]
+fn smoke_times_out() {
+    async_io::block_on(async {
+        let err = my_will_time_out_fn().await.err().unwrap();
+        assert!(matches!(err, MyErr::Timeout(_)));
+        let err = my_will_time_out_const().await.err().unwrap();
+        assert!(matches!(err, MyErr::Timeout(_)));
+    });
+}
+
+#[timeout(duration = "1ms", on_error = "panic", runtime = "smol")]
+pub async fn my_will_panic_fn() {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+}
+
+#[test]
+#[should_panic(expected = "'my_will_panic_fn' timed out after 1ms")]
+fn panics_on_timeout() {
+    async_io::block_on(async {
+        my_will_panic_fn().await;
+    });
+}
+
+static LOGGED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+fn log_timeout(_msg: &'static str) {
+    LOGGED.store(true, std::sync::atomic::Ordering::SeqCst);
+}
+
+#[timeout(duration = "1ms", on_error = "default", log = log_timeout, runtime = "smol")]
+pub async fn my_will_time_out_default_logged() -> Option<u8> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Some(1)
+}
+
+#[test]
+fn default_on_timeout() {
+    async_io::block_on(async {
+        assert!(my_will_time_out_default_logged().await.is_none());
+        assert!(LOGGED.load(std::sync::atomic::Ordering::SeqCst));
+    });
+}
+
+#[timeout(duration = "1ms", on_error = "into", runtime = "smol")]
+pub async fn my_will_time_out_io() -> std::io::Result<()> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(duration = crate::MY_DUR, on_error = "into", runtime = "smol")]
+pub async fn my_will_time_out_anyhow() -> anyhow::Result<()> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[test]
+fn into_on_timeout() {
+    async_io::block_on(async {
+        let err = my_will_time_out_io().await.unwrap_err();
+        assert_eq!(std::io::ErrorKind::TimedOut, err.kind());
+        let err = my_will_time_out_anyhow().await.unwrap_err();
+        let err = err.downcast::<tokio_timeout::TimeoutError>().unwrap();
+        assert_eq!("my_will_time_out_anyhow", err.fn_name());
+        assert_eq!(MY_DUR, err.duration());
+    });
+}
+
+fn ctx_err(
+    ctx: tokio_timeout::TimeoutContext<tokio_timeout::Elapsed>,
+) -> Result<(), tokio_timeout::TimeoutContext<tokio_timeout::Elapsed>> {
+    Err(ctx)
+}
+
+#[timeout(duration = crate::MY_DUR, on_error_ctx = ctx_err, runtime = "smol")]
+pub async fn my_will_time_out_ctx(
+) -> Result<(), tokio_timeout::TimeoutContext<tokio_timeout::Elapsed>> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[test]
+fn ctx_on_timeout() {
+    async_io::block_on(async {
+        let ctx = my_will_time_out_ctx().await.unwrap_err();
+        assert_eq!("my_will_time_out_ctx", ctx.fn_name);
+        assert_eq!(module_path!(), ctx.module_path);
+        assert_eq!(file!(), ctx.file);
+        assert_eq!(MY_DUR, ctx.duration);
+        assert!(ctx.elapsed >= MY_DUR);
+        assert_eq!("deadline has elapsed", ctx.error.to_string());
+    });
+}
+
+pub struct CachedClient {
+    cached: Vec<u8>,
+}
+
+impl CachedClient {
+    #[timeout(duration = "1ms", fallback = self.cached.clone(), runtime = "smol")]
+    pub async fn will_time_out_fetch(&self) -> Vec<u8> {
+        async_io::Timer::after(Duration::from_millis(1000)).await;
+        vec![1, 2, 3]
+    }
+}
+
+#[test]
+fn fallback_on_timeout() {
+    async_io::block_on(async {
+        let client = CachedClient { cached: vec![4] };
+        assert_eq!(vec![4], client.will_time_out_fetch().await);
+    });
+}
+
+static FLAKY_CALLS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+#[timeout(
+    duration = "5ms",
+    on_error = my_err,
+    retries = 2,
+    backoff = "1ms",
+    backoff_strategy = "exponential",
+    runtime = "smol"
+)]
+pub async fn my_flaky_fn(hang_until: u32) -> Result<String, MyErr> {
+    if FLAKY_CALLS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1 < hang_until {
+        async_io::Timer::after(Duration::from_millis(1000)).await;
+    }
+    Ok(String::new())
+}
+
+fn my_owned_err(msg: &'static str) -> Result<(), String> {
+    Err(msg.to_string())
+}
+
+#[timeout(duration = "1ms", on_error = my_owned_err, retries = 2, runtime = "smol")]
+pub async fn my_will_exhaust_retries() -> Result<(), String> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(
+    duration = "10ms",
+    on_error = my_owned_err,
+    retries = 10,
+    backoff = "5ms",
+    total = "30ms",
+    runtime = "smol"
+)]
+pub async fn my_will_exhaust_budget() -> Result<(), String> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[test]
+fn retries_on_timeout() {
+    async_io::block_on(async {
+        assert!(my_flaky_fn(3).await.is_ok());
+        assert_eq!(3, FLAKY_CALLS.load(std::sync::atomic::Ordering::SeqCst));
+        let msg = my_will_exhaust_retries().await.unwrap_err();
+        assert!(msg.contains("3 of 3 attempts timed out"), "{msg}");
+        let msg = my_will_exhaust_budget().await.unwrap_err();
+        assert!(msg.contains("total budget of"), "{msg}");
+    });
+}
+
+static SLOW_WARNINGS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+fn count_slow(_msg: &'static str) {
+    SLOW_WARNINGS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+}
+
+#[timeout(
+    duration = "1s",
+    on_error = "panic",
+    warn_after = "1ms",
+    on_slow = count_slow,
+    runtime = "smol"
+)]
+pub async fn my_slow_fn(sleep_ms: u64) -> u64 {
+    async_io::Timer::after(Duration::from_millis(sleep_ms)).await;
+    sleep_ms
+}
+
+#[test]
+fn warns_when_slow() {
+    async_io::block_on(async {
+        assert_eq!(20, my_slow_fn(20).await);
+        assert_eq!(1, SLOW_WARNINGS.load(std::sync::atomic::Ordering::SeqCst));
+    });
+}
+
+#[timeout(duration = Duration::from_millis(timeout_ms), on_error = my_err, runtime = "smol")]
+pub async fn my_arg_timeout_fn(timeout_ms: u64, sleep_ms: u64) -> Result<String, MyErr> {
+    async_io::Timer::after(Duration::from_millis(sleep_ms)).await;
+    Ok(String::new())
+}
+
+#[test]
+fn expression_durations() {
+    async_io::block_on(async {
+        assert!(my_arg_timeout_fn(1, 1000).await.is_err());
+        assert!(my_arg_timeout_fn(1000, 1).await.is_ok());
+    });
+}
+
+#[timeout(
+    duration_env = "TOKIO_TIMEOUT_SMOL_TEST_ENV_DURATION",
+    duration = "1h",
+    on_error = my_err,
+    runtime = "smol"
+)]
+pub async fn my_env_timeout_fn() -> Result<String, MyErr> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(String::new())
+}
+
+#[test]
+fn env_durations() {
+    async_io::block_on(async {
+        std::env::set_var("TOKIO_TIMEOUT_SMOL_TEST_ENV_DURATION", "1ms");
+        assert!(my_env_timeout_fn().await.is_err());
+    });
+}
+
+#[timeout(duration = "1.5ms", on_error = |msg: &'static str| Err(msg), runtime = "smol")]
+pub async fn my_default_message_fn() -> Result<(), &'static str> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(
+    duration = "1ms",
+    on_error = |msg: &'static str| Err(msg),
+    message = "{fn} exceeded {duration}",
+    runtime = "smol"
+)]
+pub async fn my_templated_message_fn() -> Result<(), &'static str> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[test]
+fn message_templates() {
+    async_io::block_on(async {
+        assert_eq!(
+            "'my_default_message_fn' timed out after 1.5ms",
+            my_default_message_fn().await.unwrap_err()
+        );
+        assert_eq!(
+            "my_templated_message_fn exceeded 1ms",
+            my_templated_message_fn().await.unwrap_err()
+        );
+    });
+}
+
+#[timeout(
+    duration = "20ms",
+    jitter = "10ms",
+    on_error = "into",
+    runtime = "smol"
+)]
+pub async fn my_jittered_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[test]
+fn jittered_timeouts() {
+    async_io::block_on(async {
+        let dur = my_jittered_fn().await.unwrap_err().duration();
+        assert!(
+            (Duration::from_millis(10)..=Duration::from_millis(30)).contains(&dur),
+            "{dur:?}"
+        );
+    });
+}
+
+#[timeout(duration = "1.5ms", on_error = "into", runtime = "smol")]
+pub async fn my_fractional_timeout_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[test]
+fn fractional_durations() {
+    async_io::block_on(async {
+        let err = my_fractional_timeout_fn().await.unwrap_err();
+        assert_eq!(Duration::from_micros(1500), err.duration());
+    });
+}
+
+#[timeout(duration = "500µs", on_error = "into", runtime = "smol")]
+pub async fn my_micros_timeout_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[timeout(duration = "1w1d", on_error = "into", runtime = "smol")]
+pub async fn my_days_timeout_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    Ok(())
+}
+
+#[test]
+fn small_and_large_units() {
+    async_io::block_on(async {
+        let err = my_micros_timeout_fn().await.unwrap_err();
+        assert_eq!(Duration::from_micros(500), err.duration());
+        assert!(my_days_timeout_fn().await.is_ok());
+    });
+}
+
+#[timeout(duration = "2 millis 500 usec", on_error = "into", runtime = "smol")]
+pub async fn my_long_form_timeout_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[test]
+fn long_form_durations() {
+    async_io::block_on(async {
+        let err = my_long_form_timeout_fn().await.unwrap_err();
+        assert_eq!(Duration::from_micros(2500), err.duration());
+    });
+}
+
+#[timeout(duration = "PT0.0025S", on_error = "into", runtime = "smol")]
+pub async fn my_iso_timeout_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[test]
+fn iso8601_durations() {
+    async_io::block_on(async {
+        let err = my_iso_timeout_fn().await.unwrap_err();
+        assert_eq!(Duration::from_micros(2500), err.duration());
+    });
+}
+
+#[timeout(
+    strict,
+    duration = "1ms 500us",
+    on_error = "into",
+    retries = 1,
+    backoff = "1ms",
+    runtime = "smol"
+)]
+pub async fn my_strict_timeout_fn() -> Result<(), tokio_timeout::TimeoutError> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[test]
+fn strict_durations() {
+    async_io::block_on(async {
+        let err = my_strict_timeout_fn().await.unwrap_err();
+        assert_eq!(Duration::from_micros(1500), err.duration());
+    });
+}
+
+#[timeout(
+    duration = Duration::from_millis(timeout_ms),
+    on_error = "into",
+    jitter = "50%",
+    min = "15ms",
+    max = "25ms",
+    runtime = "smol"
+)]
+pub async fn my_jittered_clamped_fn(timeout_ms: u64) -> Result<(), tokio_timeout::TimeoutError> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[test]
+fn jittered_timeouts_stay_within_bounds() {
+    async_io::block_on(async {
+        let bounds = Duration::from_millis(15)..=Duration::from_millis(25);
+        for _ in 0..10 {
+            // Jittered within 10ms..=30ms, then clamped
+            let dur = my_jittered_clamped_fn(20).await.unwrap_err().duration();
+            assert!(bounds.contains(&dur), "{dur:?}");
+        }
+        assert_eq!(
+            Duration::from_millis(15),
+            my_jittered_clamped_fn(0).await.unwrap_err().duration()
+        );
+        assert_eq!(
+            Duration::from_millis(25),
+            my_jittered_clamped_fn(u64::MAX)
+                .await
+                .unwrap_err()
+                .duration()
+        );
+    });
+}
+
+#[timeout(
+    duration = "1ms",
+    jitter = "500us",
+    on_error = "panic",
+    runtime = "smol"
+)]
+pub async fn my_jittered_panic_fn() {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+}
+
+#[test]
+#[should_panic(expected = "timed out after 1ms \u{b1}500us, effective timeout")]
+fn jittered_panic_reports_effective_timeout() {
+    async_io::block_on(my_jittered_panic_fn());
+}
+
+#[timeout(
+    duration = Duration::from_millis(timeout_ms),
+    on_error = "panic",
+    min = "2ms",
+    runtime = "smol"
+)]
+pub async fn my_clamped_panic_fn(timeout_ms: u64) {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+}
+
+#[test]
+#[should_panic(expected = ", effective timeout 2ms")]
+fn clamped_panic_reports_effective_timeout() {
+    async_io::block_on(my_clamped_panic_fn(0));
+}
+
+#[timeout(
+    duration = Duration::from_millis(timeout_ms),
+    on_error = |msg: &str| Err(msg.to_string()),
+    max = "2ms",
+    runtime = "smol"
+)]
+pub async fn my_clamped_message_fn(timeout_ms: u64) -> Result<(), String> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[test]
+fn clamped_message_reports_effective_timeout() {
+    async_io::block_on(async {
+        let msg = my_clamped_message_fn(u64::MAX).await.unwrap_err();
+        assert!(msg.ends_with(", effective timeout 2ms"), "{msg}");
+    });
+}
+
+static CLAMPS: std::sync::Mutex<Vec<tokio_timeout::DurationClamp>> =
+    std::sync::Mutex::new(Vec::new());
+
+fn record_clamp(clamp: tokio_timeout::DurationClamp) {
+    CLAMPS.lock().unwrap().push(clamp);
+}
+
+#[timeout(
+    duration = Duration::from_millis(timeout_ms),
+    on_error = "into",
+    min = "2ms",
+    max = "5ms",
+    on_clamp = record_clamp,
+    runtime = "smol"
+)]
+pub async fn my_clamped_fn(timeout_ms: u64) -> Result<(), tokio_timeout::TimeoutError> {
+    async_io::Timer::after(Duration::from_millis(1000)).await;
+    Ok(())
+}
+
+#[test]
+fn clamped_durations() {
+    async_io::block_on(async {
+        let clamped = |ms| async move { my_clamped_fn(ms).await.unwrap_err().duration() };
+        assert_eq!(Duration::from_millis(2), clamped(0).await);
+        assert_eq!(Duration::from_millis(3), clamped(3).await);
+        assert_eq!(Duration::from_millis(5), clamped(u64::MAX).await);
+        let clamps = CLAMPS.lock().unwrap().clone();
+        assert_eq!(2, clamps.len());
+        assert_eq!("my_clamped_fn", clamps[0].fn_name);
+        assert_eq!(Duration::ZERO, clamps[0].configured);
+        assert_eq!(Duration::from_millis(2), clamps[0].clamped);
+        assert_eq!(Duration::from_millis(u64::MAX), clamps[1].configured);
+        assert_eq!(Duration::from_millis(5), clamps[1].clamped);
+    });
+}
+
+#[timeout(duration = timeout, on_error = "into", runtime = "smol")]
+pub async fn my_optional_fn(timeout: Option<Duration>) -> Result<u32, tokio_timeout::TimeoutError> {
+    async_io::Timer::after(Duration::from_millis(20)).await;
+    Ok(3)
+}
+
+#[test]
+fn unbounded_durations() {
+    async_io::block_on(async {
+        assert_eq!(Ok(3), my_optional_fn(None).await);
+        assert_eq!(
+            Duration::from_millis(1),
+            my_optional_fn(Some(Duration::from_millis(1)))
+                .await
+                .unwrap_err()
+                .duration()
+        );
+    });
+}